@@ -3,24 +3,112 @@ use eframe::egui;
 use std::fs::{self, File};
 use std::io::Read;
 use std::path::{Path, PathBuf};
-use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
 use std::time::{Duration, Instant};
 use egui::{Align, Direction, PointerButton, Rect};
-use image::{DynamicImage, ImageFormat};
-use pdfium_render::prelude::Pixels;
-use crate::config::{AppSettings, LastPageAction, MangaAction, PageViewOptions, ResizeMethod, Shortcut, SourceMode};
+use image::DynamicImage;
+use crate::config::{AppSettings, CropMargins, ImageAdjustments, LastPageAction, LibrarySort, MangaAction, NightColorPages, PageViewOptions, RecentFile, ResizeMethod, ResumeMode, Shortcut, SortKey, SortOrder, SourceMode};
+use crate::book_settings::{BookSettings, BookSettingsStore};
 use crate::font;
-use crate::utils::{windows_natural_sort, windows_natural_sort_strings};
+use crate::imaging;
+use crate::pages::{blank_anchor, build_pages, remap_anchor, HalfSide, PageEntry};
+use crate::panels::{self, PanelRect};
+use crate::tiles::TiledImage;
+use crate::source;
+use crate::thumbnails::ThumbnailCache;
+use crate::library::{walk_sources, Library};
+use crate::book_db::{now_secs, BookDb, BookId, Bookmark, ReadStatus, ReadingHistory};
+use crate::session::Session;
+use crate::ordering::sort_paths;
+use crate::filename::ParsedName;
+use crate::watcher::FolderWatcher;
+use crate::curation::{self, Curation};
+use crate::page_loader::{self, LoadTimings, PageLoader, PageRecipe, PageSpec, PreparedPage, PreparedPair};
+
+/// Unfiltered pages kept for the adjustment preview (current pair and both buffers)
+const ADJUST_PREVIEW_PAGES: usize = 6;
+
+const MIN_ZOOM: f32 = 0.5;
+const MAX_ZOOM: f32 = 8.0;
+/// Zoom change of one zoom in/out key press
+const ZOOM_STEP: f32 = 1.25;
+/// Part of the view moved by one pan key press
+const PAN_STEP: f32 = 0.15;
+/// Wait for the zoom to settle before decoding the pages at the new resolution
+const ZOOM_SETTLE_TIME: Duration = Duration::from_millis(300);
+/// Part of the view filled by a panel in panel view
+const PANEL_FILL: f32 = 0.95;
+/// Darkness of the page around the current panel
+const PANEL_DIM_ALPHA: u8 = 200;
+/// Hand drawn panels smaller than this part of the page are taken as misclicks
+const MIN_DRAWN_PANEL_AREA: f32 = 0.002;
+/// Edge segments of the magnifier circle
+const LOUPE_SEGMENTS: u32 = 64;
+/// Height of the page thumbnails shown while dragging the page slider
+const SCRUB_PREVIEW_HEIGHT: f32 = 200.0;
+/// Thumbnails decoded ahead on both sides of the slider handle
+const SCRUB_PREFETCH: usize = 4;
+/// Height of the page thumbnails in the overview grid
+const OVERVIEW_THUMB_HEIGHT: f32 = 180.0;
+/// Width of a page slot in the overview grid, relative to its height
+const OVERVIEW_PAGE_ASPECT: f32 = 0.7;
+/// Size of a book in the library grid, without its caption
+const LIBRARY_COVER_SIZE: egui::Vec2 = egui::vec2(150.0, 210.0);
+/// How long the "Resumed at page" notice offers to start over
+const RESUME_NOTICE_TIME: Duration = Duration::from_secs(6);
+const BOOKMARK_COLOR: egui::Color32 = egui::Color32::from_rgb(230, 80, 60);
+/// How long the notice of a move, copy, trash or rename offers to undo it
+const CURATION_NOTICE_TIME: Duration = Duration::from_secs(8);
+
+/// What a file or folder picked in a dialog other than "Open File" is for
+#[derive(Clone, Copy)]
+enum PickPurpose {
+    LibraryRoot,
+    NavigationRoot,
+    CurationTarget,
+    ExportBookmarks,
+    ImportBookmarks,
+}
+
+/// Whether the open target folder list moves or copies the book
+#[derive(Clone, Copy, PartialEq)]
+enum TargetPicker {
+    Move,
+    Copy,
+}
+
+/// Spread detection running in the background for the open book
+struct SpreadScan {
+    source: PathBuf,
+    rx: Receiver<Vec<bool>>,
+    /// Page shown while waiting and the page the reading history asked for, which may only
+    /// exist once the spreads are split
+    resume: Option<(usize, usize)>,
+}
 
 pub struct MangaReader {
     zip_path: Option<PathBuf>,
     image_files: Vec<String>,
+    pages: Vec<PageEntry>,
+    spread_pages: Vec<bool>,
+    spread_scan: Option<SpreadScan>,
     current_index: usize,
     textures: [Option<egui::TextureHandle>; 2],
     buffer_next: [Option<egui::TextureHandle>; 2],
     buffer_prev: [Option<egui::TextureHandle>; 2],
     last_buffered_index: Option<usize>,
     all_zips_in_folder: Vec<PathBuf>,
+    /// Books next to the open one being sorted on a worker: (their folder, sorted books)
+    folder_sort: Option<Receiver<(PathBuf, Vec<PathBuf>)>>,
+    /// Sources below a navigation root in walking order, for next and previous folder
+    source_walk: Option<(PathBuf, Vec<PathBuf>)>,
+    folder_watcher: Option<FolderWatcher>,
+    target_picker: Option<TargetPicker>,
+    rename_input: Option<String>,
+    /// Asking before trashing, when the key can be hit by accident
+    trash_confirm: bool,
+    last_curation: Option<Curation>,
+    curation_notice: Option<Instant>,
     error_msg: Option<(String, Instant)>,
     is_fullscreen: bool,
     can_scroll: bool,
@@ -34,21 +122,93 @@ pub struct MangaReader {
     config: AppSettings,
     binding_action: Option<String>,
     texture_cache: std::collections::HashMap<String, egui::TextureHandle>,
+    /// The page each cached texture shared its top and bottom crop with, `None` when cropped alone
+    crop_partners: std::collections::HashMap<String, Option<String>>,
     initial_path: Option<PathBuf>,
     source_mode: SourceMode,
     last_image_switch_time: Instant,
     zoom_factor: f32,
+    texture_zoom: f32,
+    zoom_changed_time: Option<Instant>,
+    scroll_offset: egui::Vec2,
+    pending_scroll_offset: Option<egui::Vec2>,
+    view_rect: Rect,
+    content_size: egui::Vec2,
+    pan_animation: Option<(egui::Vec2, egui::Vec2, Instant)>,
     is_scrubbing: bool,
+    book_settings: BookSettings,
+    /// Page view of the open book from its reading history, `None` follows the settings
+    view_mode: Option<PageViewOptions>,
+    book_settings_store: BookSettingsStore,
+    adjust_bases: std::collections::VecDeque<(String, DynamicImage)>,
+    day_visuals: Option<egui::Visuals>,
+    last_scheduled_night: Option<bool>,
+    panel_view: bool,
+    /// Current panel of the visible pages, `usize::MAX` for the last one
+    panel_index: usize,
+    /// Page `panel_index` belongs to, any other page starts at its first panel
+    panel_page: Option<usize>,
+    panel_animation: Option<(usize, PanelRect, Instant)>,
+    /// Detected panels, keyed by the texture cache name
+    panel_cache: std::collections::HashMap<String, Vec<PanelRect>>,
+    /// Margins cut from each loaded page, keyed by the texture cache name
+    page_crops: std::collections::HashMap<String, CropMargins>,
+    panel_edit: bool,
+    panel_drag_start: Option<egui::Pos2>,
+    /// Screen rect of each visible page, for the magnifier
+    page_rects: [Option<Rect>; 2],
+    /// Full resolution copies of the visible pages, keyed by their texture names
+    loupe_textures: [Option<egui::TextureHandle>; 2],
+    loupe_key: Option<[Option<String>; 2]>,
+    /// Pages the loupe textures are being made for, with the job of `loupe_loader`
+    pending_loupe: Option<([Option<String>; 2], u64)>,
+    loupe_loader: PageLoader,
+    /// Pages larger than the GPU texture limit, keyed by the texture cache name
+    tiled_pages: std::collections::HashMap<String, TiledImage>,
+    /// Pair start and loader job of the pages after a jump, with the thumbnails standing in
+    pending_full_load: Option<(usize, u64, [Option<usize>; 2])>,
+    page_loader: PageLoader,
+    load_timings: LoadTimings,
+    thumbnails: ThumbnailCache,
+    /// Page under the slider handle while it is dragged
+    scrub_target: Option<usize>,
+    page_overview: bool,
+    /// First page of the spread selected in the overview grid
+    overview_selected: usize,
+    /// Scroll the overview grid to the selection on the next frame
+    overview_scroll: bool,
+    show_library: bool,
+    library: Library,
+    library_filter: String,
+    pick_rx: Receiver<(PickPurpose, Option<PathBuf>)>,
+    pick_tx: Sender<(PickPurpose, Option<PathBuf>)>,
+    book_db: BookDb,
+    /// Identity of the open book in `book_db`
+    book_id: Option<BookId>,
+    /// Page, shift and view mode last written to the history, to notice changes
+    history_state: Option<(usize, bool, Option<PageViewOptions>)>,
+    /// Page the open book was left at, with the time the offer to resume or start over appeared
+    resume_prompt: Option<(usize, Instant)>,
+    /// Session of the last run, applied once its book is open
+    pending_session: Option<Session>,
+    /// Window position and size while it is neither maximized nor fullscreen
+    window_geometry: Option<([f32; 2], [f32; 2])>,
+    window_maximized: bool,
+    /// Book, page, shift and view mode last written to the session file
+    session_state: Option<(Option<PathBuf>, usize, bool, Option<PageViewOptions>)>,
+    show_bookmarks: bool,
+    /// New tag being typed for the open book
+    tag_input: String,
 }
 
 impl MangaReader {
-    pub fn new(_cc: &eframe::CreationContext<'_>, initial_path: Option<PathBuf>) -> Self {
+    pub fn new(_cc: &eframe::CreationContext<'_>, initial_path: Option<PathBuf>, session: Option<Session>) -> Self {
         font::setup_custom_fonts(&_cc.egui_ctx);
         let mut exe_path = env::current_exe().expect("Failed to get current exe path");
         exe_path.pop();
         exe_path.push("settings.json");
         eprintln!("Loading setting from : {:?}", exe_path.to_str());
-        let config: AppSettings = if let Ok(data) = std::fs::read_to_string(exe_path) {
+        let mut config: AppSettings = if let Ok(data) = std::fs::read_to_string(exe_path) {
             // add |_| here to accept the error argument but ignore it
             serde_json::from_str(&data).unwrap_or_else(|e| {
                 eprintln!("Error is : {:?}", e);
@@ -59,17 +219,32 @@ impl MangaReader {
             AppSettings::default()
         };
 
+        // Forget the files that were moved or deleted since the last run
+        config.recent_files.retain(|recent| recent.path.exists());
+
         let (tx, rx) = channel();
+        let (pick_tx, pick_rx) = channel();
         Self {
             initial_path,
             zip_path: None,
             image_files: Vec::new(),
+            pages: Vec::new(),
+            spread_pages: Vec::new(),
+            spread_scan: None,
             current_index: 0,
             textures: [None, None],
             buffer_next: [None, None],
             buffer_prev: [None, None],
             last_buffered_index: None,
             all_zips_in_folder: Vec::new(),
+            folder_sort: None,
+            source_walk: None,
+            folder_watcher: None,
+            target_picker: None,
+            rename_input: None,
+            trash_confirm: false,
+            last_curation: None,
+            curation_notice: None,
             error_msg: None,
             dialog_rx: rx,
             dialog_tx: tx,
@@ -83,10 +258,61 @@ impl MangaReader {
             config, // Store the loaded config here
             binding_action: None,
             texture_cache: Default::default(),
+            crop_partners: Default::default(),
             source_mode: SourceMode::Zip,
             last_image_switch_time: Instant::now(),
             zoom_factor: 1.0,
+            texture_zoom: 1.0,
+            zoom_changed_time: None,
+            scroll_offset: egui::Vec2::ZERO,
+            pending_scroll_offset: None,
+            view_rect: Rect::ZERO,
+            content_size: egui::Vec2::ZERO,
+            pan_animation: None,
             is_scrubbing: false,
+            book_settings: BookSettings::default(),
+            view_mode: None,
+            book_settings_store: BookSettingsStore::load(),
+            adjust_bases: Default::default(),
+            day_visuals: None,
+            last_scheduled_night: None,
+            panel_view: false,
+            panel_index: 0,
+            panel_page: None,
+            panel_animation: None,
+            panel_cache: Default::default(),
+            page_crops: Default::default(),
+            panel_edit: false,
+            panel_drag_start: None,
+            page_rects: [None, None],
+            loupe_textures: [None, None],
+            loupe_key: None,
+            pending_loupe: None,
+            loupe_loader: PageLoader::new(page_loader::prepare_full_pair),
+            tiled_pages: Default::default(),
+            pending_full_load: None,
+            page_loader: PageLoader::new(page_loader::prepare_pair),
+            load_timings: LoadTimings::default(),
+            thumbnails: ThumbnailCache::new(),
+            scrub_target: None,
+            page_overview: false,
+            overview_selected: 0,
+            overview_scroll: false,
+            show_library: false,
+            library: Library::new(),
+            library_filter: String::new(),
+            pick_rx,
+            pick_tx,
+            book_db: BookDb::load(),
+            book_id: None,
+            history_state: None,
+            resume_prompt: None,
+            window_geometry: session.as_ref().and_then(|s| s.window_pos.zip(s.window_size)),
+            window_maximized: session.as_ref().map_or(true, |s| s.maximized),
+            pending_session: session,
+            session_state: None,
+            show_bookmarks: false,
+            tag_input: String::new(),
         }
     }
 
@@ -119,7 +345,7 @@ impl MangaReader {
         }
     }
 
-    fn scan_folder(&mut self, current_parent: &Path) -> Vec<PathBuf> {
+    fn scan_folder(&mut self, current_parent: &Path, ctx: &egui::Context) -> Vec<PathBuf> {
         let mut items = Vec::new();
         if let Ok(entries) = fs::read_dir(current_parent) {
             for entry in entries.flatten() {
@@ -133,67 +359,49 @@ impl MangaReader {
                 }
             }
         }
-        windows_natural_sort(&mut items);
+        let order = self.config.file_sorting.order_for(current_parent);
+        self.folder_sort = None;
+        if order.key == SortKey::ComicInfo {
+            // Reading the ComicInfo opens every book, that is done on a worker like the
+            // library scan. The books are in name order until then.
+            let (tx, rx) = channel();
+            let mut sorted = items.clone();
+            let folder = current_parent.to_path_buf();
+            let ctx = ctx.clone();
+            std::thread::spawn(move || {
+                sort_paths(&mut sorted, order);
+                let _ = tx.send((folder, sorted));
+                ctx.request_repaint();
+            });
+            self.folder_sort = Some(rx);
+            sort_paths(&mut items, SortOrder { key: SortKey::NameNatural, ..order });
+        } else {
+            sort_paths(&mut items, order);
+        }
         items
     }
 
-
-    fn get_adjacent_directories(path_with_file_name: Option<PathBuf>) -> (Option<PathBuf>, Option<PathBuf>) {
-        // Unwrap the Option to get the actual PathBuf
-        let path = match path_with_file_name {
-            Some(p) => p,
-            None => return (None, None),
-        };
-
-        let path = match path.parent() {
-            Some(p) => p,
-            None => return (None, None),
-        };
-
-        let root_dir = match path.parent() {
-            Some(p) => p,
-            None => return (None, None),
-        };
-
-        // Collect all valid sibling directories
-        let mut dirs: Vec<PathBuf> = fs::read_dir(root_dir)
-            .ok()
-            .map(|read_dir| {
-                read_dir
-                    .filter_map(|entry| {
-                        let p = entry.ok()?.path();
-                        // Ensure it's a directory and not a hidden file
-                        if p.is_dir() && !p.file_name()?.to_str()?.starts_with('.') {
-                            Some(p)
-                        } else {
-                            None
-                        }
-                    })
-                    .collect()
-            })
-            .unwrap_or_default();
-
-        // Sort using Windows natural alphanumeric order (test2 before test10)
-        // If you haven't added a crate, dirs.sort() works for simple cases
-        windows_natural_sort(&mut *dirs);
-
-        // Find where we are
-        let current_pos = dirs.iter().position(|p| *p == path);
-
-        match current_pos {
-            Some(pos) => {
-                let prev = if pos > 0 { Some(dirs[pos - 1].clone()) } else { None };
-                let next = if pos + 1 < dirs.len() { Some(dirs[pos + 1].clone()) } else { None };
-                (prev, next)
+    /// Take the books next to the open one once the worker sorted them
+    fn poll_folder_sort(&mut self) {
+        let Some(rx) = &self.folder_sort else { return };
+        match rx.try_recv() {
+            Ok((folder, books)) => {
+                self.folder_sort = None;
+                if self.zip_path.as_ref().and_then(|p| p.parent()) == Some(folder.as_path()) {
+                    self.all_zips_in_folder = books;
+                }
             }
-            None => (None, None),
+            Err(TryRecvError::Empty) => {}
+            Err(TryRecvError::Disconnected) => self.folder_sort = None,
         }
     }
 
+
     fn update_buffers(&mut self, ctx: &egui::Context) {
         let idx = self.current_index;
 
-        if self.is_scrubbing {
+        // Wait for the pages on screen to be done first
+        if self.is_scrubbing || self.pending_full_load.is_some() {
             return;
         }
 
@@ -201,10 +409,6 @@ impl MangaReader {
             return;
         }
 
-        if self.zoom_factor != 1.0 {
-            return;
-        }
-
         // Only update if we moved OR if the buffers were recently consumed
         if self.last_buffered_index == Some(idx) {
             return;
@@ -241,338 +445,337 @@ impl MangaReader {
         }}
 
         let mut pair: [Option<egui::TextureHandle>; 2] = [None, None];
-        let source_path = self.zip_path.clone().unwrap();
-
-        let mut archive = if self.source_mode == SourceMode::Zip {
-            let path = source_path.clone();
-            File::open(path).ok().and_then(|f| zip::ZipArchive::new(f).ok())
-        } else {
-            None
-        };
-
-        for i in 0..2 {
-            let current_target = start_idx + i;
-            if let Some(filename) = self.image_files.get(current_target) {
-                if let Some(handle) = self.texture_cache.get(filename) {
-                    pair[i] = Some(handle.clone());
-                    continue;
-                }
-
-                if self.source_mode == SourceMode::Pdf {
-                    let dynamic_image = self.render_pdf_page(current_target, ctx);
-                    if let Some(img) = dynamic_image {
-                        pair[i] = self.load_texture(img, filename.clone(), ctx);
-                    }
-                } else {
-                    let bytes = if self.source_mode == SourceMode::Folder {
-                        fs::read(filename).ok() // Load directly from path
-                    } else if let Some(ref mut arc) = archive {
-                        arc.by_name(filename).ok().and_then(|mut f| {
-                            let mut b = Vec::new();
-                            f.read_to_end(&mut b).ok().map(|_| b)
-                        })
-                    } else  if self.source_mode == SourceMode::Rar {
-                        unrar::Archive::new(&source_path).open_for_processing().ok().and_then(|rar_achive| {
-                            let mut cursor = rar_achive.read_header().ok().flatten();
-                            loop {
-                                match cursor {
-                                    Some(e) => {
-                                        // Use .entry() before reference filename
-                                        let current_name = e.entry().filename.to_str();
-
-                                        if let Some(name_str) = current_name {
-                                            if name_str == filename {
-                                                break e.read().ok().map(|(bytes, _arc)| bytes);
-                                            } else {
-                                                cursor = e.skip().ok().and_then(|arc| arc.read_header().ok().flatten());
-                                            }
-                                        } else {
-                                            // Filename wasn't valid UTF-8, skip it
-                                            cursor = e.skip().ok().and_then(|arc| arc.read_header().ok().flatten());
-                                        }
-                                    }
-                                    None => break None,
-                                }
-                            }
-                        })
-                    } else {
-                        None
-                    };
-
-                    if let Some(mut buffer) = bytes {
-                        if self.config.enable_auto_image_byte_fix {
-                            buffer = self.strip_adobe_app14_if_invalid(&buffer);
-                        }
-                        match image::guess_format(&buffer) {
-                            Ok(format) => {
-                                if let Ok(img) = image::load_from_memory_with_format(&buffer, format) {
-                                    pair[i] = self.load_texture(img, filename.clone(), ctx);
-                                }
-                            }
-                            Err(_) => {
-                                // Fallback: If guessing fails, try loading as TGA
-                                // since TGA is often the one that fails detection.
-                                if let Ok(img) = image::load_from_memory_with_format(&buffer, ImageFormat::Tga) {
-                                    pair[i] = self.load_texture(img, filename.clone(), ctx);
-                                }
-                            }
-                        }
-                    }
-                }
+        let specs = self.pair_specs(start_idx, Some(&mut pair));
+        let prepared = page_loader::prepare_pair(&self.page_recipe(ctx), &specs);
+        let mut timings = prepared.timings;
+        let upload_start = Instant::now();
+        let textures = self.upload_pair(prepared, ctx);
+        timings.upload += upload_start.elapsed();
+        for (slot, texture) in pair.iter_mut().zip(textures) {
+            if texture.is_some() {
+                *slot = texture;
             }
         }
+
+        // Only the timings of the pages on screen are kept, not the ones of the buffers
+        if start_idx == self.current_index {
+            self.load_timings = timings;
+        }
         pair
     }
 
-    fn strip_adobe_app14_if_invalid(&self, bytes: &[u8]) -> Vec<u8> {
-        let mut i = 2;
-        let mut out = Vec::with_capacity(bytes.len());
+    /// Like `load_pair`, for jumps far away: the thumbnails of the pages are shown right away,
+    /// and replaced by the full quality pages once the worker made them
+    fn load_pair_progressive(&mut self, start_idx: usize, ctx: &egui::Context) -> [Option<egui::TextureHandle>; 2] {
+        let mut pair: [Option<egui::TextureHandle>; 2] = [None, None];
+        let specs = self.pair_specs(start_idx, Some(&mut pair));
+        if specs.iter().all(Option::is_none) {
+            // Everything came from the cache
+            return pair;
+        }
 
-        // Copy SOI first (must be first two bytes)
-        if bytes.len() < 2 || bytes[0] != 0xFF || bytes[1] != 0xD8 {
-            return bytes.to_vec(); // not a valid jpeg
+        // Halves of a scan have no thumbnail of their own. Panel view waits for the page, the
+        // panels are found by the name of its texture.
+        let preview_start = Instant::now();
+        let previews = specs.each_ref().map(|spec| {
+            spec.as_ref().filter(|spec| spec.half.is_none() && !self.panel_view).map(|spec| spec.file_index)
+        });
+        for (i, spec) in specs.iter().enumerate() {
+            let (Some(spec), Some(file_index)) = (spec, previews[i]) else { continue };
+            self.thumbnails.request(file_index, &spec.filename);
+            pair[i] = self.thumbnails.get(file_index).cloned();
         }
 
-        out.extend_from_slice(&bytes[0..2]);
+        let id = self.page_loader.submit(self.page_recipe(ctx), specs, ctx);
+        self.load_timings = LoadTimings { preview: preview_start.elapsed(), ..Default::default() };
+        self.pending_full_load = Some((start_idx, id, previews));
+        pair
+    }
 
-        while i < bytes.len() {
-            if i + 1 >= bytes.len() {
-                break;
+    /// Second stage of a progressive load: the full quality pages take the place of the thumbnails
+    fn finish_progressive_load(&mut self, ctx: &egui::Context) {
+        // Keep showing the thumbnails while the slider is dragged
+        let Some((start_idx, id, previews)) = self.pending_full_load else { return };
+        if self.is_scrubbing {
+            return;
+        }
+        if start_idx != self.current_index {
+            // Moved on before the pages were done
+            self.pending_full_load = None;
+            return;
+        }
+        let Some(prepared) = self.page_loader.poll(id) else {
+            // Thumbnails that were not ready when the jump started
+            for (slot, file_index) in previews.into_iter().enumerate() {
+                if let (None, Some(file_index)) = (&self.textures[slot], file_index) {
+                    self.textures[slot] = self.thumbnails.get(file_index).cloned();
+                }
             }
-
-            // Every marker must start with FF
-            if bytes[i] != 0xFF {
-                // Start of entropy data (after SOS)
-                out.extend_from_slice(&bytes[i..]);
-                break;
+            return;
+        };
+        self.pending_full_load = None;
+        let preview = self.load_timings.preview;
+        self.load_timings = LoadTimings { preview, ..prepared.timings };
+        let upload_start = Instant::now();
+        let textures = self.upload_pair(prepared, ctx);
+        self.load_timings.upload += upload_start.elapsed();
+        for (slot, texture) in self.textures.iter_mut().zip(textures) {
+            if texture.is_some() {
+                *slot = texture;
             }
+        }
+    }
 
-            let marker = bytes[i + 1];
+    /// The pages starting at `start_idx` that need to be made. Pages found in the texture cache
+    /// are put in `cached` instead, when given.
+    fn pair_specs(&self, start_idx: usize, mut cached: Option<&mut [Option<egui::TextureHandle>; 2]>) -> [Option<PageSpec>; 2] {
+        let mut specs: [Option<PageSpec>; 2] = [None, None];
+        if self.zip_path.is_none() {
+            return specs;
+        }
 
-            // Standalone markers (no length)
-            if marker == 0xD9 || (0xD0..=0xD7).contains(&marker) {
-                out.push(0xFF);
-                out.push(marker);
-                i += 2;
-                continue;
-            }
+        // A cached page only fits when it was cropped with the same partner. Pages sharing
+        // their crop are taken from the cache together or made together.
+        let names = [0, 1].map(|i| self.page_cache_name(start_idx + i));
+        let shared = self.shared_crop() && names.iter().all(Option::is_some);
+        let partners = [1, 0].map(|other| if shared { names[other].clone() } else { None });
+        let fits = |i: usize| names[i].as_ref().is_some_and(|name| {
+            self.texture_cache.contains_key(name) && self.crop_partners.get(name) == Some(&partners[i])
+        });
+        if shared && !(fits(0) && fits(1)) {
+            cached = None;
+        }
 
-            // SOS marker → copy rest of file and stop parsing
-            if marker == 0xDA {
-                out.extend_from_slice(&bytes[i..]);
-                break;
+        for i in 0..2 {
+            // Blank pages and out of range pages leave the slot empty
+            let Some(page) = self.pages.get(start_idx + i) else { continue };
+            let Some(file_index) = page.file_index() else { continue };
+            let Some(filename) = self.image_files.get(file_index).cloned() else { continue };
+            let Some(cache_name) = names[i].clone() else { continue };
+
+            if let Some(cached) = cached.as_mut() {
+                if let Some(handle) = self.texture_cache.get(&cache_name).filter(|_| fits(i)) {
+                    cached[i] = Some(handle.clone());
+                    continue;
+                }
             }
 
-            if i + 4 > bytes.len() {
-                break;
-            }
+            let half = match page {
+                PageEntry::Half(_, side) => Some(*side),
+                _ => None,
+            };
+            let detect_panels = self.panel_view
+                && !self.panel_cache.contains_key(&cache_name)
+                && !self.book_settings.panels.contains_key(&cache_name);
+            specs[i] = Some(PageSpec { file_index, filename, cache_name, half, detect_panels });
+        }
+        specs
+    }
 
-            let length =
-                u16::from_be_bytes([bytes[i + 2], bytes[i + 3]]) as usize;
+    /// The settings the pages are made with, for `page_loader`
+    fn page_recipe(&self, ctx: &egui::Context) -> PageRecipe {
+        let screen_height = ctx.content_rect().height();
+        let max_texture_side = ctx.input(|i| i.max_texture_side);
+        PageRecipe {
+            mode: self.source_mode,
+            source: self.zip_path.clone().unwrap_or_default(),
+            byte_fix: self.config.enable_auto_image_byte_fix,
+            // Pdf pages are rendered for the screen height and zoom
+            pdf_height: (screen_height * self.texture_zoom.max(1.0)).min(max_texture_side as f32),
+            crop_override: self.book_settings.crop_override,
+            auto_crop: self.config.auto_crop.then_some(self.config.auto_crop_tolerance),
+            shared_crop: self.shared_crop(),
+            filter: self.config.resize_method.to_filter(),
+            dither: self.config.resize_dither,
+            screen_height,
+            texture_zoom: self.texture_zoom,
+            max_texture_side: max_texture_side as u32,
+            keep_base: self.config.show_settings,
+            adjustments: self.adjustments(),
+            night_mode: self.config.night_mode.then_some((self.config.night_color_pages, self.config.night_warmth)),
+            transparency_support: self.config.transparency_support,
+            right_to_left: self.page_view() != PageViewOptions::DoubleLR,
+        }
+    }
 
-            let segment_end = i + 2 + length;
+    /// Name of a page in the texture cache: the file name, with the side for halves of a scan
+    fn page_cache_name(&self, index: usize) -> Option<String> {
+        let page = self.pages.get(index)?;
+        let filename = self.image_files.get(page.file_index()?)?;
+        Some(match page {
+            PageEntry::Half(_, side) => format!("{}#{:?}", filename, side),
+            _ => filename.clone(),
+        })
+    }
 
-            if segment_end > bytes.len() {
-                break;
-            }
+    /// Whether two facing pages get the same top and bottom crop
+    fn shared_crop(&self) -> bool {
+        (self.book_settings.crop_override.is_some() || self.config.auto_crop) && !self.is_single_page()
+    }
 
-            // If APP14 (FF EE)
-            if marker == 0xEE
-                && length >= 14
-                && &bytes[i + 4..i + 9] == b"Adobe"
-            {
-                println!("Stripping Adobe APP14 segment");
-                // Skip this segment entirely
-                i = segment_end;
-                continue;
+    /// Upload the pages the loader made, noting which pages were cropped together
+    fn upload_pair(&mut self, prepared: PreparedPair, ctx: &egui::Context) -> [Option<egui::TextureHandle>; 2] {
+        let names = prepared.pages.each_ref().map(|page| page.as_ref().map(|page| page.cache_name.clone()));
+        for (i, name) in names.iter().enumerate() {
+            if let Some(name) = name {
+                let partner = if prepared.shared_crop { names[1 - i].clone() } else { None };
+                self.crop_partners.insert(name.clone(), partner);
             }
-
-            // Otherwise copy full segment
-            out.extend_from_slice(&bytes[i..segment_end]);
-            i = segment_end;
         }
-
-        out
+        prepared.pages.map(|page| page.map(|page| self.upload_page(page, ctx)))
     }
 
-    /// Helper to render a specific page
-    fn render_pdf_page(&self, index: usize, ctx: &egui::Context) -> Option<DynamicImage> {
-        // You would typically store a reference to the loaded document
-        // and render the page here.
-        let pdfium = pdfium_render::prelude::Pdfium::default();
-        let doc = pdfium.load_pdf_from_file(self.zip_path.as_ref()?, None).ok()?;
-        let page = doc.pages().get(index as u16).ok()?;
-        let width_inch = page.width().value ;
-        let height_inch = page.height().value;
+    fn upload_page(&mut self, page: PreparedPage, ctx: &egui::Context) -> egui::TextureHandle {
+        let PreparedPage { cache_name, crop, image, full_image, base, panels } = page;
+        self.page_crops.insert(cache_name.clone(), crop);
+        if let Some(panels) = panels {
+            self.panel_cache.entry(cache_name.clone()).or_insert(panels);
+        }
 
-        let screen_size = ctx.content_rect();
-        let target_h = screen_size.height();
-        let h_ratio = target_h / height_inch;
-        let target_w = width_inch * h_ratio;
+        // Keep the unfiltered pages while the settings are open, for the live preview
+        if let Some(base) = base {
+            if self.adjust_bases.len() >= ADJUST_PREVIEW_PAGES {
+                self.adjust_bases.pop_front();
+            }
+            self.adjust_bases.push_back((cache_name.clone(), base));
+        }
 
-        // Render at 300 DPI or based on screen height for clarity
-        let bitmap = page.render(target_w as Pixels, target_h as Pixels, None).ok()?;
-        Some(bitmap.as_image()) // pdfium-render integrates with the 'image' crate
+        match full_image {
+            Some(full_image) => {
+                let options = self.texture_options(full_image.height() as u32, ctx);
+                self.tiled_pages.insert(cache_name.clone(), TiledImage::new(full_image, options));
+            }
+            None => {
+                self.tiled_pages.remove(&cache_name);
+            }
+        }
+        let options = self.texture_options(image.height() as u32, ctx);
+        let handle = ctx.load_texture(&cache_name, image, options);
+        if self.config.enable_single_file_caching {
+            self.texture_cache.insert(cache_name, handle.clone());
+        }
+        handle
     }
 
-    fn load_texture(&mut self, img: DynamicImage, cache_name:String, ctx: &egui::Context) -> Option<egui::TextureHandle> {
-        let resize_start = Instant::now();
-        let filter = self.config.resize_method.to_filter();
-        let processed_img = if let Some(filter_type) = filter {
-            let screen_size = ctx.content_rect();
-            let target_h = screen_size.height() as u32;
-            let aspect_ratio = img.width() as f32 / img.height() as f32;
-            let target_w = (target_h as f32 * aspect_ratio) as u32;
-            let factor = if self.zoom_factor != 1.0 {3} else {1};
-            img.resize(target_w * factor, target_h * factor, filter_type)
-        } else {
-            img // No resizing needed, return original
-        };
-
-        let _resize_time = resize_start.elapsed();
-        let process_start = Instant::now();
-
-        let size = [processed_img.width() as _, processed_img.height() as _];
-        let color_img = if self.config.transparency_support {
-            egui::ColorImage::from_rgba_unmultiplied(
-                size,
-                processed_img.to_rgba8().as_flat_samples().as_slice(),
-            )
+    /// Smooth scaling, except when zoomed far into a low resolution page:
+    /// then the real pixels are shown instead of a blur.
+    fn texture_options(&self, texture_height: u32, ctx: &egui::Context) -> egui::TextureOptions {
+        let display_height = ctx.content_rect().height() * self.zoom_factor;
+        if self.zoom_factor >= 2.0 && display_height > texture_height as f32 * 2.0 {
+            egui::TextureOptions {
+                magnification: egui::TextureFilter::Nearest,
+                ..egui::TextureOptions::LINEAR
+            }
         } else {
-            egui::ColorImage::from_rgb(
-                size,
-                processed_img.to_rgb8().as_raw()
-            )
-        };
+            egui::TextureOptions::LINEAR
+        }
+    }
 
-        let _process_time = process_start.elapsed();
+    /// Adjustments of the current book, or the global ones when it has none
+    fn adjustments(&self) -> ImageAdjustments {
+        self.book_settings.adjustments.unwrap_or(self.config.adjustments)
+    }
 
-        #[cfg(debug_assertions)]
-        {
-            println!("----------------------------------");
-            println!("resize_time: {:?}", _resize_time);
-            println!("process_time: {:?}", _process_time);
-            println!("total: {:?}", _process_time + _resize_time);
-            println!("filter: {:?}", filter);
-            println!("----------------------------------");
-        }
+    /// The adjustments, then night mode
+    fn apply_filters(&self, img: DynamicImage) -> DynamicImage {
+        let night_mode = self.config.night_mode.then_some((self.config.night_color_pages, self.config.night_warmth));
+        page_loader::apply_filters(img, &self.adjustments(), night_mode)
+    }
 
-        let handle = ctx.load_texture(
-            &cache_name.clone(),
-            color_img,
-            egui::TextureOptions::LINEAR // Smooth scaling
-        );
-        if self.config.enable_single_file_caching {
-            self.texture_cache.insert(cache_name.clone(), handle.clone());
+    /// Re-apply the adjustments to the pages on screen, without decoding them again
+    fn preview_adjustments(&mut self, ctx: &egui::Context) {
+        let transparency_support = self.config.transparency_support;
+        for i in 0..self.textures.len() {
+            let Some(name) = self.textures[i].as_ref().map(|tex| tex.name()) else { continue };
+            let Some(img) = self.adjust_bases.iter()
+                .find(|(base_name, _)| *base_name == name)
+                .map(|(_, base)| self.apply_filters(base.clone())) else { continue };
+            let options = self.texture_options(img.height(), ctx);
+            // The tiles keep the old filters, show the stand-in until the page is reloaded
+            self.tiled_pages.remove(&name);
+            if let Some(tex) = self.textures[i].as_mut() {
+                tex.set(page_loader::to_color_image(&img, transparency_support), options);
+            }
         }
-        Some(handle)
     }
 
     fn load_source(&mut self, path: PathBuf, ctx: &egui::Context) {
         let mut target_path = path.clone();
         let mut start_at_filename: Option<String> = None;
 
-        let mut images = Vec::new();
-        let exts = ["png", "jpg", "jpeg", "bmp", "webp", "gif", "tiff", "tga", "avif"];
-        let extension = path.extension().map_or("".to_string(), |ext| ext.to_string_lossy().to_lowercase());
-        match extension.as_str() {
-            "zip" | "cbz" => {
-                let file = match File::open(&target_path) {
-                    Ok(f) => f,
-                    Err(_) => return,
-                };
-                if let Ok(mut archive) = zip::ZipArchive::new(file) {
-                    for i in 0..archive.len() {
-                        if let Ok(f) = archive.by_index(i) {
-                            let name = f.name().to_lowercase();
-                            if exts.iter().any(|&e| name.ends_with(&format!(".{}", e))) {
-                                images.push(f.name().to_string());
-                            }
-                        }
-                    }
-                    self.source_mode = SourceMode::Zip;
-                }
-                self.source_mode = SourceMode::Zip;
-                // ... existing zip logic
-            }
-            "cbr" | "rar" => {
-                self.source_mode = SourceMode::Rar;
-                if let Ok(archive) = unrar::Archive::new(&path).open_for_listing() {
-                    for entry in archive {
-                        if let Ok(e) = entry {
-                            // Convert Option<&str> to String safely
-                            if let Some(name_str) = e.filename.to_str() {
-                                let name = name_str.to_string();
-                                // Check if it's an image extension
-                                if exts.iter().any(|&e_ext| name.to_lowercase().ends_with(e_ext)) {
-                                    images.push(name);
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-            "pdf" => {
-                // --- PDF MODE ---
-                // Initialize Pdfium (you may need to bundle the dll/so/dylib)
-                let pdfium = pdfium_render::prelude::Pdfium::default();
-                if let Ok(doc) = pdfium.load_pdf_from_file(&path, None) {
-                    let page_count = doc.pages().len();
-                    for i in 0..page_count {
-                        // We use a virtual naming scheme for PDF pages in our image_files list
-                        images.push(format!("pdf_page_{}", i));
-                    }
-                }
-                self.source_mode = SourceMode::Pdf;
-            }
-            _ => {
-                if path.is_file() {
-                    // Pivot: Use the folder containing this image as the source
-                    if let Some(parent) = path.parent() {
-                        start_at_filename = Some(path.to_string_lossy().to_string());
-                        target_path = parent.to_path_buf();
-                    }
-                }
-
-                if target_path.is_dir() {
-                    // --- FOLDER MODE ---
-                    if let Ok(entries) = fs::read_dir(&target_path) {
-                        for entry in entries.flatten() {
-                            let p = entry.path();
-                            if p.is_file() && exts.iter().any(|&e| p.extension().map_or(false, |ext| ext.to_string_lossy().to_lowercase() == e)) {
-                                images.push(p.to_string_lossy().to_string());
-                            }
-                        }
-                    }
-                }
-                self.source_mode = SourceMode::Folder;
+        let mode = source::source_mode_of(&path);
+        if mode == SourceMode::Folder && path.is_file() {
+            // Pivot: Use the folder containing this image as the source
+            if let Some(parent) = path.parent() {
+                start_at_filename = Some(path.to_string_lossy().to_string());
+                target_path = parent.to_path_buf();
             }
         }
-
-        windows_natural_sort_strings(&mut images);
+        self.source_mode = mode;
+        let images = source::list_pages(mode, &target_path, self.config.file_sorting.order_for(&target_path));
 
         if images.is_empty() {
             self.show_fading_error("No images found in selection.");
         } else {
             self.reset_buffer();
             self.texture_cache.clear();
+            self.crop_partners.clear();
+            self.panel_cache.clear();
+            self.page_crops.clear();
+            self.forget_loupe();
+            self.panel_page = None;
+            self.panel_edit = false;
 
             // If we opened a specific image, find its index in the sorted list
-            self.current_index = if let Some(target_name) = start_at_filename {
-                images.iter().position(|r| r == &target_name).unwrap_or(0)
-            } else {
-                0
-            };
+            let start_file_index = start_at_filename.and_then(|target_name| images.iter().position(|r| r == &target_name));
 
             self.zip_path = Some(target_path.clone());
+            self.thumbnails.set_source(self.source_mode, target_path.clone(), self.config.enable_auto_image_byte_fix, ctx);
+            self.page_overview = false;
             self.image_files = images;
 
+            // Restore the page layout saved for this book
+            self.book_settings = self.book_settings_store.get(&target_path);
+            self.book_db.save();
+            let book_id = BookId::of(&target_path);
+            let history = self.book_db.get(&book_id).and_then(|record| record.history.clone());
+            if let Some(history) = &history {
+                self.is_shifted = history.is_shifted;
+            }
+            self.view_mode = history.as_ref().and_then(|history| history.view_mode);
+            self.book_id = Some(book_id);
+            self.locate_bookmarks();
+            self.add_recent_file(&target_path);
+            self.history_state = None;
+            self.resume_prompt = None;
+            self.spread_pages.clear();
+            self.spread_scan = None;
+            self.update_page_list(ctx);
+
+            self.current_index = start_file_index
+                .and_then(|file_index| self.pages.iter().position(|p| p.file_index() == Some(file_index)))
+                .unwrap_or(0);
+
+            // Opening a specific image wins over the history
+            let saved_page = history.as_ref().map_or(0, |h| h.last_page);
+            let last_page = saved_page.min(self.pages.len() - 1);
+            if start_file_index.is_none() && last_page > 0 && self.config.resume_mode != ResumeMode::StartOver {
+                if self.config.resume_mode == ResumeMode::Resume {
+                    self.current_index = last_page;
+                }
+                self.resume_prompt = Some((last_page, Instant::now()));
+                if let Some(scan) = self.spread_scan.as_mut() {
+                    scan.resume = Some((self.current_index, saved_page));
+                }
+            }
+
             // Scan parent for Next/Prev file navigation
-            self.all_zips_in_folder = self.scan_folder(&target_path.parent().unwrap_or(Path::new("")));
+            self.all_zips_in_folder = self.scan_folder(&target_path.parent().unwrap_or(Path::new("")), ctx);
+            // Pick up pages and books that are still being copied in
+            let pages_dir = (self.source_mode == SourceMode::Folder).then_some(target_path.as_path());
+            self.folder_watcher = target_path.parent().and_then(|parent| FolderWatcher::new(pages_dir, parent, ctx));
 
-            if let Some(file_name) = target_path.file_name() {
-                self.zip_name_display = Some((file_name.to_string_lossy().to_string(), Instant::now()));
-            }
+            self.zip_name_display = Some((ParsedName::display_name(&target_path), Instant::now()));
 
             self.textures = self.load_pair(self.current_index, ctx);
         }
@@ -592,7 +795,7 @@ impl MangaReader {
         }
         let step = if self.is_single_page() || (self.is_shifted && self.current_index == 0) { 1 } else { 2 };
 
-        if self.current_index + step < self.image_files.len() {
+        if self.current_index + step < self.pages.len() {
             self.current_index += step;
             // If the next pages are already in the buffer, swap them in
             if self.buffer_next[0].is_some() {
@@ -645,11 +848,16 @@ impl MangaReader {
         self.page_indicator_time = Some(Instant::now());
     }
 
+    fn is_finished(&self, path: &Path) -> bool {
+        self.book_db.read_status(&BookId::of(path)) == ReadStatus::Finished
+    }
+
     fn next_zip(&mut self, ctx: &egui::Context) {
         if let Some(pos) = self.all_zips_in_folder.iter().position(|p| Some(p) == self.zip_path.as_ref()) {
-            if pos + 1 < self.all_zips_in_folder.len() {
+            let skip_finished = self.config.skip_finished_files;
+            let next = self.all_zips_in_folder[pos + 1..].iter().find(|p| !skip_finished || !self.is_finished(p)).cloned();
+            if let Some(next_path) = next {
                 // There is a next file
-                let next_path = self.all_zips_in_folder[pos + 1].clone();
                 self.load_source(next_path, ctx);
             } else {
                 // NO MORE FILES - This is the fix
@@ -660,8 +868,9 @@ impl MangaReader {
 
     fn prev_zip(&mut self, ctx: &egui::Context) {
         if let Some(pos) = self.all_zips_in_folder.iter().position(|p| Some(p) == self.zip_path.as_ref()) {
-            if pos > 0 {
-                let prev_path = self.all_zips_in_folder[pos - 1].clone();
+            let skip_finished = self.config.skip_finished_files;
+            let prev = self.all_zips_in_folder[..pos].iter().rev().find(|p| !skip_finished || !self.is_finished(p)).cloned();
+            if let Some(prev_path) = prev {
                 // We pass 'true' to load_zip so it knows to start at the end of the new file
                 self.load_source(prev_path, ctx);
             } else {
@@ -670,56 +879,106 @@ impl MangaReader {
         }
     }
 
-    fn next_folder(&mut self, ctx: &egui::Context) {
-        let (_, next_dir) = Self::get_adjacent_directories(self.zip_path.clone());
-
-        // Check if next_dir actually exists
-        if let Some(dir) = next_dir {
-            let zips = self.scan_folder(&*dir);
-            if zips.is_empty() {
-                self.show_fading_error("No Archive found in next folder.");
-            } else {
-                self.load_source(zips[0].clone(), ctx);
+    /// Next file in the folder that isn't finished yet
+    fn next_unread(&mut self, ctx: &egui::Context) {
+        if let Some(pos) = self.all_zips_in_folder.iter().position(|p| Some(p) == self.zip_path.as_ref()) {
+            let next = self.all_zips_in_folder[pos + 1..].iter().find(|p| !self.is_finished(p)).cloned();
+            match next {
+                Some(next_path) => self.load_source(next_path, ctx),
+                None => self.show_fading_error("No unread files left in folder."),
             }
-        } else {
-            self.show_fading_error("No next directory found.");
         }
     }
 
-    fn prev_folder(&mut self, ctx: &egui::Context) {
-        let (prev_dir, _) = Self::get_adjacent_directories(self.zip_path.clone());
+    /// Folder whose sources next/prev folder walk through: the configured root, a library
+    /// root holding the book, or else the folder above the book's own folder
+    fn navigation_root(&self, source: &Path) -> Option<PathBuf> {
+        self.config.navigation_root.iter()
+            .chain(self.config.library_roots.iter())
+            .find(|root| source.starts_with(root) && source != root.as_path())
+            .cloned()
+            .or_else(|| source.parent()?.parent().map(Path::to_path_buf))
+    }
 
-        // Check if next_dir actually exists
-        if let Some(dir) = prev_dir {
-            let zips = self.scan_folder(&*dir);
-            if zips.is_empty() {
-                self.show_fading_error("No Archive found in previous folder.");
-            } else {
-                self.load_source(zips[0].clone(), ctx);
-            }
-        } else {
-            self.show_fading_error("No previous directory found.");
+    /// Position of `current` among the sources below `root`, depth first, with those sources.
+    /// The walk is kept, and only done again for another root or when the book is not in it.
+    fn walk_position(&mut self, root: &Path, current: &Path) -> Option<(usize, &[PathBuf])> {
+        let walked = self.source_walk.as_ref()
+            .is_some_and(|(walked_root, sources)| walked_root == root && sources.iter().any(|p| p == current));
+        if !walked {
+            let mut sources = Vec::new();
+            walk_sources(root, &self.config.file_sorting, &mut |path, _| {
+                sources.push(path.to_path_buf());
+                true
+            });
+            self.source_walk = Some((root.to_path_buf(), sources));
+        }
+        let sources = &self.source_walk.as_ref()?.1;
+        let index = sources.iter().position(|p| p == current)?;
+        Some((index, sources))
+    }
+
+    /// First source after the current one, depth first below the navigation root, that is in another folder
+    fn next_folder(&mut self, ctx: &egui::Context) {
+        let Some(current) = self.zip_path.clone() else { return };
+        let Some(root) = self.navigation_root(&current) else {
+            self.show_fading_error("No folder above this one.");
+            return;
+        };
+        let Some((index, sources)) = self.walk_position(&root, &current) else {
+            self.show_fading_error("This file is not a source of the library root.");
+            return;
+        };
+        let folder = current.parent();
+        let next = sources[index + 1..].iter().find(|p| p.parent() != folder && p.exists()).cloned();
+
+        match next {
+            Some(next_path) => self.load_source(next_path, ctx),
+            None => self.show_fading_error("This is the last folder in the library."),
         }
     }
 
+    /// First source of the folder before the current one, depth first below the navigation root
+    fn prev_folder(&mut self, ctx: &egui::Context) {
+        let Some(current) = self.zip_path.clone() else { return };
+        let Some(root) = self.navigation_root(&current) else {
+            self.show_fading_error("No folder above this one.");
+            return;
+        };
+        let Some((index, sources)) = self.walk_position(&root, &current) else {
+            self.show_fading_error("This file is not a source of the library root.");
+            return;
+        };
+        // Books removed since the walk are passed over
+        let folder = current.parent();
+        let Some(last) = sources[..index].iter().rposition(|p| p.parent() != folder && p.exists()) else {
+            self.show_fading_error("This is the first folder in the library.");
+            return;
+        };
+        let prev_folder = sources[last].parent();
+        let first = sources[..last].iter().rposition(|p| p.parent() != prev_folder).map_or(0, |i| i + 1);
+        let Some(target) = sources[first..=last].iter().find(|p| p.exists()).cloned() else { return };
+        self.load_source(target, ctx);
+    }
+
 
     fn go_to_first_page(&mut self, ctx: &egui::Context) {
-        if !self.image_files.is_empty() && self.current_index != 0 {
+        if !self.pages.is_empty() && self.current_index != 0 {
             self.reset_buffer();
             self.current_index = 0;
-            self.textures = self.load_pair(self.current_index, ctx);
+            self.textures = self.load_pair_progressive(self.current_index, ctx);
             self.page_indicator_time = Some(Instant::now());
         }
     }
 
     fn go_to_last_page(&mut self, ctx: &egui::Context) {
-        if !self.image_files.is_empty() {
+        if !self.pages.is_empty() {
             // Find the last possible pair start (must be an even index)
-            let last_idx = (self.image_files.len().saturating_sub(1) / 2) * 2;
+            let last_idx = (self.pages.len().saturating_sub(1) / 2) * 2;
             if self.current_index != last_idx {
                 self.reset_buffer();
                 self.current_index = last_idx;
-                self.textures = self.load_pair(self.current_index, ctx);
+                self.textures = self.load_pair_progressive(self.current_index, ctx);
                 self.page_indicator_time = Some(Instant::now());
             }
         }
@@ -728,6 +987,7 @@ impl MangaReader {
     fn reset_buffer(&mut self) {
         self.buffer_prev = [None, None];
         self.buffer_next = [None, None];
+        self.pending_full_load = None;
     }
 
     fn create_image_rect(&mut self, ui: &mut egui::Ui, rect: Rect, hit_id: &str, is_next: bool, tex_index: usize, ctx: &egui::Context, align: egui::Align) {
@@ -745,37 +1005,1989 @@ impl MangaReader {
             // Render the image on top
             if let Some(tex) = &self.textures[tex_index] {
                 let layout = egui::Layout::top_down(align);
-                ui.with_layout(layout, |ui| {
+                let image_rect = ui.with_layout(layout, |ui| {
                     ui.add(egui::Image::new(tex)
                         .fit_to_exact_size(rect.size())
-                        .maintain_aspect_ratio(true));
-                });
+                        .maintain_aspect_ratio(true)).rect
+                }).inner;
+                self.page_rects[tex_index] = Some(image_rect);
+                self.paint_tiles(ui.painter(), tex_index, image_rect);
             }
         });
     }
 
+    /// Page view of the open book, or the one of the settings
+    fn page_view(&self) -> PageViewOptions {
+        self.view_mode.unwrap_or(self.config.page_view_options)
+    }
+
     fn is_single_page(&self) -> bool {
-        self.config.page_view_options == PageViewOptions::Single
+        self.page_view() == PageViewOptions::Single
     }
 
     fn change_shifted_mode(&mut self, ctx: &egui::Context) {
         self.is_shifted = !self.is_shifted;
 
-        // Adjust current_index to keep the view consistent
-        if self.is_shifted {
-            if self.current_index == 0 { self.current_index = 0; }
-            else if self.current_index % 2 == 0 { self.current_index += 1; }
-        } else {
-            // Return to even index alignment
-            self.current_index = self.current_index.saturating_sub(1);
-            if self.current_index % 2 != 0 { self.current_index = self.current_index.saturating_sub(1); }
+        // Adjust current_index to keep the view consistent
+        if self.is_shifted {
+            if self.current_index == 0 { self.current_index = 0; }
+            else if self.current_index % 2 == 0 { self.current_index += 1; }
+        } else {
+            // Return to even index alignment
+            self.current_index = self.current_index.saturating_sub(1);
+            if self.current_index % 2 != 0 { self.current_index = self.current_index.saturating_sub(1); }
+        }
+
+        self.reset_buffer();
+        self.texture_cache.clear();
+        self.textures = self.load_pair(self.current_index, ctx);
+        let msg = if self.is_shifted { "Mode: Odd Page" } else { "Mode: Even Page" };
+        self.show_fading_error(msg);
+    }
+
+    /// Build `pages` from the image list and the layout settings of the current book
+    /// Spreads are detected on a worker, the pages stay whole until it is done
+    fn update_page_list(&mut self, ctx: &egui::Context) {
+        let split = self.book_settings.split_spreads.unwrap_or(self.config.split_spreads);
+        if split && self.spread_pages.len() != self.image_files.len() {
+            self.spread_pages.clear();
+            self.start_spread_scan(ctx);
+        }
+        let spreads: &[bool] = if split { &self.spread_pages } else { &[] };
+        let right_first = self.page_view() != PageViewOptions::DoubleLR;
+        self.pages = build_pages(self.image_files.len(), &self.book_settings.blank_pages, spreads, right_first);
+    }
+
+    fn start_spread_scan(&mut self, ctx: &egui::Context) {
+        let Some(source) = self.zip_path.clone() else { return };
+        if self.spread_scan.as_ref().is_some_and(|scan| scan.source == source) {
+            return;
+        }
+        let (tx, rx) = channel();
+        let mode = self.source_mode;
+        let image_files = self.image_files.clone();
+        let path = source.clone();
+        let ctx = ctx.clone();
+        std::thread::spawn(move || {
+            let _ = tx.send(scan_spreads(mode, &path, &image_files));
+            ctx.request_repaint();
+        });
+        self.spread_scan = Some(SpreadScan { source, rx, resume: None });
+    }
+
+    /// Split the spreads once the worker found them
+    fn poll_spread_scan(&mut self, ctx: &egui::Context) {
+        let Some(spreads) = self.spread_scan.as_ref().and_then(|scan| scan.rx.try_recv().ok()) else { return };
+        let Some(scan) = self.spread_scan.take() else { return };
+        if self.zip_path.as_ref() != Some(&scan.source) || spreads.len() != self.image_files.len() {
+            return;
+        }
+        let found = spreads.contains(&true);
+        self.spread_pages = spreads;
+        if !found {
+            return;
+        }
+        match scan.resume {
+            // Still on the resumed page, go to where the history really was
+            Some((shown, wanted)) if shown == self.current_index && self.config.resume_mode == ResumeMode::Resume => {
+                self.update_page_list(ctx);
+                self.current_index = wanted.min(self.pages.len().saturating_sub(1));
+                self.reload_textures(ctx);
+            }
+            _ => self.rebuild_pages(ctx),
+        }
+        if let (Some((_, wanted)), Some(prompt)) = (scan.resume, self.resume_prompt.as_mut()) {
+            prompt.0 = wanted.min(self.pages.len().saturating_sub(1));
+        }
+    }
+
+    /// Rebuild the page list after a layout setting changed, staying on the same image
+    fn rebuild_pages(&mut self, ctx: &egui::Context) {
+        let current_file = self.pages.get(self.current_index).and_then(|p| p.file_index());
+        self.update_page_list(ctx);
+        if let Some(file_index) = current_file {
+            self.current_index = self.pages.iter().position(|p| p.file_index() == Some(file_index)).unwrap_or(0);
+        }
+        self.current_index = self.current_index.min(self.pages.len().saturating_sub(1));
+
+        // Cropped textures depend on the page they were paired with
+        self.reload_textures(ctx);
+        self.page_indicator_time = Some(Instant::now());
+    }
+
+    /// Read the image list of the open folder again, staying on the image on screen.
+    /// `changed` are the files the watcher saw written, added or removed.
+    fn refresh_folder_pages(&mut self, changed: &[PathBuf], ctx: &egui::Context) {
+        let Some(folder) = self.zip_path.clone() else { return };
+        let images = source::list_pages(SourceMode::Folder, &folder, self.config.file_sorting.order_for(&folder));
+        if images.is_empty() {
+            self.close_source();
+            self.show_fading_error("No images left in the folder.");
+            return;
+        }
+
+        // A page still being written was cached half drawn, decode it again
+        let is_changed = |name: &String| {
+            let file = name.strip_suffix("#Left").or_else(|| name.strip_suffix("#Right")).unwrap_or(name);
+            changed.iter().any(|path| path.as_path() == Path::new(file))
+        };
+        let rewritten = self.image_files.iter().any(is_changed);
+        self.texture_cache.retain(|name, _| !is_changed(name));
+        self.adjust_bases.retain(|(name, _)| !is_changed(name));
+        self.tiled_pages.retain(|name, _| !is_changed(name));
+        self.panel_cache.retain(|name, _| !is_changed(name));
+        self.page_crops.retain(|name, _| !is_changed(name));
+        self.crop_partners.retain(|name, partner| !is_changed(name) && !partner.as_ref().is_some_and(is_changed));
+        self.forget_loupe();
+        if images == self.image_files && !rewritten {
+            return;
+        }
+        let known: std::collections::HashSet<&String> = self.image_files.iter().collect();
+        let added = images.iter().filter(|name| !known.contains(name)).count();
+        let current_image = self.visible_file_index().and_then(|i| self.image_files.get(i)).cloned();
+
+        let old_files = std::mem::replace(&mut self.image_files, images);
+        // Blank pages and bookmarks are kept with their image, wherever it went
+        if !self.book_settings.blank_pages.is_empty() {
+            let mut blanks: Vec<usize> = self.book_settings.blank_pages.iter()
+                .map(|&anchor| remap_anchor(anchor, &old_files, &self.image_files))
+                .collect();
+            blanks.sort_unstable();
+            self.book_settings.blank_pages = blanks;
+            self.book_settings_store.set(&folder, self.book_settings.clone());
+        }
+        self.locate_bookmarks();
+        self.spread_pages.clear();
+        self.spread_scan = None;
+        self.update_page_list(ctx);
+        let file_index = current_image.and_then(|name| self.image_files.iter().position(|n| *n == name));
+        if let Some(index) = file_index.and_then(|i| self.pages.iter().position(|p| p.file_index() == Some(i))) {
+            self.current_index = index;
+        }
+        self.current_index = self.current_index.min(self.pages.len().saturating_sub(1));
+        // The history holds a page position and count, write it for the new list
+        self.history_state = None;
+
+        // Thumbnails are requested by index, the textures are cached by name and stay
+        self.thumbnails.set_source(SourceMode::Folder, folder, self.config.enable_auto_image_byte_fix, ctx);
+        self.reset_buffer();
+        self.textures = self.load_pair(self.current_index, ctx);
+        if added > 0 {
+            let msg = if added == 1 { "1 page added".to_string() } else { format!("{} pages added", added) };
+            self.show_fading_error(&msg);
+        }
+    }
+
+    /// Throw away every loaded texture and decode the current pages again
+    fn reload_textures(&mut self, ctx: &egui::Context) {
+        self.reset_buffer();
+        self.texture_cache.clear();
+        self.panel_cache.clear();
+        self.forget_loupe();
+        self.textures = self.load_pair(self.current_index, ctx);
+    }
+
+    fn set_night_mode(&mut self, enabled: bool, ctx: &egui::Context) {
+        if self.config.night_mode != enabled {
+            self.config.night_mode = enabled;
+            self.save_settings();
+            self.reload_textures(ctx);
+        }
+        self.update_night_visuals(ctx);
+        let msg = if enabled { "Night Mode: On" } else { "Night Mode: Off" };
+        self.show_fading_error(msg);
+    }
+
+    /// Dim the UI in night mode, and bring back the normal look when leaving it
+    fn update_night_visuals(&mut self, ctx: &egui::Context) {
+        if self.config.night_mode {
+            if self.day_visuals.is_none() {
+                self.day_visuals = Some(ctx.style().visuals.clone());
+            }
+            let mut visuals = egui::Visuals::dark();
+            visuals.override_text_color = Some(egui::Color32::from_gray(120));
+            visuals.panel_fill = egui::Color32::from_gray(12);
+            visuals.window_fill = egui::Color32::from_gray(16);
+            visuals.extreme_bg_color = egui::Color32::from_gray(8);
+            visuals.widgets.inactive.weak_bg_fill = egui::Color32::from_gray(30);
+            visuals.widgets.inactive.bg_fill = egui::Color32::from_gray(30);
+            ctx.set_visuals(visuals);
+        } else if let Some(visuals) = self.day_visuals.take() {
+            ctx.set_visuals(visuals);
+        }
+    }
+
+    /// Switch night mode on and off at the scheduled hours. A manual toggle stays
+    /// until the next scheduled change.
+    fn check_night_schedule(&mut self, ctx: &egui::Context) {
+        if !self.config.night_schedule {
+            self.last_scheduled_night = None;
+            return;
+        }
+
+        use chrono::Timelike;
+        let hour = chrono::Local::now().hour();
+        let (start, end) = (self.config.night_start_hour, self.config.night_end_hour);
+        let is_night = if start <= end {
+            (start..end).contains(&hour)
+        } else {
+            // Wraps around midnight, e.g. 21 -> 7
+            hour >= start || hour < end
+        };
+
+        if self.last_scheduled_night != Some(is_night) {
+            self.last_scheduled_night = Some(is_night);
+            if self.config.night_mode != is_night {
+                self.set_night_mode(is_night, ctx);
+            }
+        }
+        ctx.request_repaint_after(Duration::from_secs(60));
+    }
+
+    fn is_zoomed(&self) -> bool {
+        (self.zoom_factor - 1.0).abs() > 0.01
+    }
+
+    /// Change the zoom, keeping the point under `anchor` (or the view center) in place
+    fn zoom_at(&mut self, zoom: f32, anchor: Option<egui::Pos2>) {
+        let zoom = zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+        if zoom == self.zoom_factor {
+            return;
+        }
+
+        let ratio = zoom / self.zoom_factor;
+        let anchor = anchor.filter(|p| self.view_rect.contains(*p)).unwrap_or(self.view_rect.center()) - self.view_rect.min;
+        self.pending_scroll_offset = Some(((self.scroll_offset + anchor) * ratio - anchor).max(egui::Vec2::ZERO));
+        self.zoom_factor = zoom;
+        self.zoom_changed_time = Some(Instant::now());
+    }
+
+    /// Move the zoomed view, `direction` is in view sizes
+    fn pan(&mut self, direction: egui::Vec2) {
+        let step = self.view_rect.size() * PAN_STEP;
+        self.pending_scroll_offset = Some((self.scroll_offset + direction * step).max(egui::Vec2::ZERO));
+    }
+
+    /// Viewport positions of a zoomed page in reading order: row by row from the top,
+    /// each row from the right for right to left books and from the left otherwise
+    fn guided_pan_positions(&self) -> Vec<egui::Vec2> {
+        let max_offset = (self.content_size - self.view_rect.size()).max(egui::Vec2::ZERO);
+        let step = self.view_rect.size() * (1.0 - self.config.pan_overlap.clamp(0.0, 0.9));
+        let stops = |max: f32, step: f32| -> Vec<f32> {
+            let count = if step > 0.0 { (max / step).ceil() as usize } else { 0 };
+            (0..=count).map(|i| if count == 0 { 0.0 } else { max * i as f32 / count as f32 }).collect()
+        };
+
+        let mut xs = stops(max_offset.x, step.x);
+        if self.page_view() != PageViewOptions::DoubleLR {
+            xs.reverse();
+        }
+        stops(max_offset.y, step.y).into_iter()
+            .flat_map(|y| xs.iter().map(move |&x| egui::vec2(x, y)))
+            .collect()
+    }
+
+    /// Walk through the zoomed page like the eye reads it, and only turn the
+    /// page after its last viewport. `forward` is false for the previous key.
+    fn guided_step(&mut self, forward: bool, ctx: &egui::Context) {
+        if self.panel_view {
+            self.panel_step(forward, ctx);
+            return;
+        }
+        if !self.config.guided_pan || !self.is_zoomed() {
+            if forward { self.next_page(ctx) } else { self.prev_page(ctx) }
+            return;
+        }
+
+        let positions = self.guided_pan_positions();
+        let current = self.pan_animation.map_or(self.scroll_offset, |(_, to, _)| to);
+        let index = positions.iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| (**a - current).length().total_cmp(&(**b - current).length()))
+            .map_or(0, |(i, _)| i);
+
+        let target = if forward { index + 1 } else { index.wrapping_sub(1) };
+        if let Some(position) = positions.get(target) {
+            self.pan_animation = Some((self.scroll_offset, *position, Instant::now()));
+            return;
+        }
+
+        // Past the last viewport, turn the page and start at its first (or last) viewport
+        let page = self.current_index;
+        if forward { self.next_page(ctx) } else { self.prev_page(ctx) }
+        if self.current_index != page {
+            let start = if forward { positions.first() } else { positions.last() };
+            self.pan_animation = None;
+            self.pending_scroll_offset = start.copied();
+        }
+    }
+
+    /// Scroll offset of the running pan animation
+    fn animate_pan(&mut self, ctx: &egui::Context) {
+        let Some((from, to, start)) = self.pan_animation else { return };
+        // Grabbing the page stops the animation
+        if ctx.input(|i| i.pointer.any_down()) {
+            self.pan_animation = None;
+            return;
+        }
+
+        let duration = self.config.pan_animation_ms.max(1) as f32 / 1000.0;
+        let t = (start.elapsed().as_secs_f32() / duration).min(1.0);
+        let eased = t * t * (3.0 - 2.0 * t); // smoothstep
+        self.pending_scroll_offset = Some(from + (to - from) * eased);
+        if t >= 1.0 {
+            self.pan_animation = None;
+        } else {
+            ctx.request_repaint();
+        }
+    }
+
+    /// Decode the pages again once the zoom settled, when the textures are
+    /// too small (or needlessly large) for the new zoom
+    fn update_zoom_textures(&mut self, ctx: &egui::Context) {
+        let Some(changed_time) = self.zoom_changed_time else { return };
+        if changed_time.elapsed() < ZOOM_SETTLE_TIME {
+            ctx.request_repaint_after(ZOOM_SETTLE_TIME);
+            return;
+        }
+        self.zoom_changed_time = None;
+
+        let wanted = self.zoom_factor.max(1.0);
+        let nearest_changed = (self.zoom_factor >= 2.0) != (self.texture_zoom >= 2.0);
+        if wanted > self.texture_zoom * 1.25 || wanted < self.texture_zoom / 1.5 || nearest_changed {
+            self.texture_zoom = wanted;
+            self.reload_textures(ctx);
+        }
+    }
+
+    /// Single page view and every zoomed view: the pages live in a scroll area
+    /// that can be dragged, scrolled and zoomed around the cursor
+    fn show_scroll_view(&mut self, ui: &mut egui::Ui, rect: Rect, single: bool, ctx: &egui::Context) {
+        let mut scroll_area = egui::ScrollArea::both()
+            .auto_shrink([false; 2])
+            .drag_to_scroll(true); // This enables the "drag the image" feature
+        self.animate_pan(ctx);
+        if let Some(offset) = self.pending_scroll_offset.take() {
+            scroll_area = scroll_area.scroll_offset(offset);
+        }
+
+        let mut page_rects = [None, None];
+        let output = scroll_area.show(ui, |ui| {
+            if single {
+                if let Some(tex) = &self.textures[0] {
+                    let tex_size = tex.size_vec2();
+                    let aspect_ratio = tex_size.x / tex_size.y;
+
+                    // Calculate size based on zoom
+                    // 1.0 zoom = screen height. 2.0 zoom = double screen height.
+                    let zoom_height = rect.height() * self.zoom_factor;
+                    let zoom_width = zoom_height * aspect_ratio;
+                    let zoom_size = egui::vec2(zoom_width, zoom_height);
+
+                    let layout = egui::Layout::centered_and_justified(Direction::TopDown);
+                    page_rects[0] = Some(ui.with_layout(layout, |ui| {
+                        ui.add(egui::Image::new(tex)
+                            .fit_to_exact_size(zoom_size)
+                            .maintain_aspect_ratio(true)).rect
+                    }).inner);
+                }
+            } else {
+                // Same layout as the double page view, scaled by the zoom
+                let zoom_size = rect.size() * self.zoom_factor;
+                let (content_rect, _) = ui.allocate_exact_size(zoom_size.max(ui.available_size()), egui::Sense::hover());
+                let pages_rect = Rect::from_center_size(content_rect.center(), zoom_size);
+                let center = pages_rect.center().x;
+                let left_half = Rect::from_min_max(pages_rect.min, egui::pos2(center, pages_rect.max.y));
+                let right_half = Rect::from_min_max(egui::pos2(center, pages_rect.min.y), pages_rect.max);
+
+                // Right to left books have the first page on the right side
+                let (left_tex, right_tex) = if self.page_view() == PageViewOptions::DoubleLR { (0, 1) } else { (1, 0) };
+                for (half, tex_index, hug_right) in [(left_half, left_tex, true), (right_half, right_tex, false)] {
+                    if let Some(tex) = &self.textures[tex_index] {
+                        let tex_size = tex.size_vec2();
+                        let size = tex_size * (half.width() / tex_size.x).min(half.height() / tex_size.y);
+                        // Both pages touch at the middle, like the unzoomed view
+                        let x = if hug_right { half.right() - size.x } else { half.left() };
+                        let image_rect = Rect::from_min_size(egui::pos2(x, half.top()), size);
+                        egui::Image::new(tex).paint_at(ui, image_rect);
+                        page_rects[tex_index] = Some(image_rect);
+                    }
+                }
+            }
+        });
+        self.scroll_offset = output.state.offset;
+        self.view_rect = output.inner_rect;
+        self.content_size = output.content_size;
+        self.page_rects = page_rects;
+        let painter = ui.painter_at(output.inner_rect);
+        for (slot, page_rect) in page_rects.into_iter().enumerate() {
+            if let Some(page_rect) = page_rect {
+                self.paint_tiles(&painter, slot, page_rect);
+            }
+        }
+
+        let resp = ui.interact(rect, ui.id().with("cover_hit"), egui::Sense::click());
+        if resp.clicked() {
+            self.next_page(ctx);
+        }
+        if resp.clicked_by(PointerButton::Secondary) {
+            self.prev_page(ctx);
+        }
+    }
+
+    fn toggle_auto_crop(&mut self, ctx: &egui::Context) {
+        self.config.auto_crop = !self.config.auto_crop;
+        self.save_settings();
+        self.reload_textures(ctx);
+        let msg = if self.config.auto_crop { "Auto Crop: On" } else { "Auto Crop: Off" };
+        self.show_fading_error(msg);
+    }
+
+    /// Insert a virtual blank page before the current page, or remove the blank page
+    /// currently on screen. The layout is saved for the book right away.
+    fn toggle_blank_page(&mut self, ctx: &egui::Context) {
+        let Some(source_path) = self.zip_path.clone() else { return };
+        if self.pages.is_empty() {
+            return;
+        }
+
+        let visible = if self.is_single_page() || (self.is_shifted && self.current_index == 0) { 1 } else { 2 };
+        let visible_end = (self.current_index + visible).min(self.pages.len());
+        let blank_pos = (self.current_index..visible_end).find(|&p| self.pages[p] == PageEntry::Blank);
+
+        let msg = if let Some(pos) = blank_pos {
+            let anchor = blank_anchor(&self.pages, pos, self.image_files.len());
+            if let Some(i) = self.book_settings.blank_pages.iter().position(|&b| b == anchor) {
+                self.book_settings.blank_pages.remove(i);
+            }
+            "Blank page removed"
+        } else {
+            let anchor = blank_anchor(&self.pages, self.current_index, self.image_files.len());
+            self.book_settings.blank_pages.push(anchor);
+            self.book_settings.blank_pages.sort_unstable();
+            "Blank page inserted"
+        };
+
+        self.update_page_list(ctx);
+        self.current_index = self.current_index.min(self.pages.len().saturating_sub(1));
+        self.book_settings_store.set(&source_path, self.book_settings.clone());
+
+        self.reload_textures(ctx);
+        self.page_indicator_time = Some(Instant::now());
+        self.show_fading_error(msg);
+    }
+
+    fn set_panel_view(&mut self, enabled: bool, ctx: &egui::Context) {
+        self.panel_view = enabled;
+        self.panel_edit = false;
+        self.panel_page = None;
+        self.panel_animation = None;
+        if enabled {
+            // Panels are detected while the pages are decoded
+            self.reload_textures(ctx);
+        }
+        let msg = if enabled { "Panel View: On" } else { "Panel View: Off" };
+        self.show_fading_error(msg);
+    }
+
+    /// Panels of one page as drawn on its texture: the hand corrected ones, else the detected ones
+    fn page_panels(&self, key: &str) -> Vec<PanelRect> {
+        // Corrections are saved on the whole page, they stay in place when the crop changes
+        let crop = self.page_crops.get(key).copied().unwrap_or_default();
+        let panels = match self.book_settings.panels.get(key) {
+            Some(saved) => saved.iter().filter_map(|panel| panel.to_cropped(crop)).collect(),
+            None => self.panel_cache.get(key).cloned().unwrap_or_default(),
+        };
+        if panels.is_empty() { vec![PanelRect::FULL_PAGE] } else { panels }
+    }
+
+    /// Panels of the visible pages in reading order, with the texture slot they are on
+    fn visible_panels(&self) -> Vec<(usize, PanelRect)> {
+        self.textures.iter()
+            .enumerate()
+            .filter_map(|(slot, tex)| tex.as_ref().map(|tex| (slot, tex.name())))
+            .flat_map(|(slot, key)| self.page_panels(&key).into_iter().map(move |panel| (slot, panel)))
+            .collect()
+    }
+
+    /// Index of the current panel, back to the first panel when the page changed
+    fn current_panel(&mut self, panel_count: usize) -> usize {
+        if self.panel_page != Some(self.current_index) {
+            self.panel_page = Some(self.current_index);
+            self.panel_index = 0;
+            self.panel_animation = None;
+        }
+        self.panel_index = self.panel_index.min(panel_count.saturating_sub(1));
+        self.panel_index
+    }
+
+    /// Move to the next (or previous) panel, turning the page after its last panel
+    fn panel_step(&mut self, forward: bool, ctx: &egui::Context) {
+        if self.panel_edit {
+            return;
+        }
+        let panels = self.visible_panels();
+        let index = self.current_panel(panels.len());
+        let target = if forward { index + 1 } else { index.wrapping_sub(1) };
+        if target < panels.len() {
+            self.panel_animation = Some((panels[index].0, panels[index].1, Instant::now()));
+            self.panel_index = target;
+            return;
+        }
+
+        let page = self.current_index;
+        if forward { self.next_page(ctx) } else { self.prev_page(ctx) }
+        if self.current_index != page {
+            // Going back starts at the last panel of the previous page
+            self.panel_page = Some(self.current_index);
+            self.panel_index = if forward { 0 } else { usize::MAX };
+            self.panel_animation = None;
+        }
+    }
+
+    /// Texture cache name of the page the current panel is on
+    fn current_panel_page(&mut self) -> Option<String> {
+        let panels = self.visible_panels();
+        let index = self.current_panel(panels.len());
+        let (slot, _) = panels.get(index)?;
+        self.textures[*slot].as_ref().map(|tex| tex.name())
+    }
+
+    /// Forget the hand corrected panels of the current page
+    fn reset_page_panels(&mut self) {
+        let (Some(source_path), Some(key)) = (self.zip_path.clone(), self.current_panel_page()) else { return };
+        if self.book_settings.panels.remove(&key).is_some() {
+            self.book_settings_store.set(&source_path, self.book_settings.clone());
+        }
+        self.panel_index = 0;
+    }
+
+    /// Show the current panel as large as possible, the rest of the page dimmed around it
+    fn show_panel_view(&mut self, ui: &mut egui::Ui, rect: Rect, ctx: &egui::Context) {
+        self.view_rect = rect;
+        self.scroll_offset = egui::Vec2::ZERO;
+
+        let panels = self.visible_panels();
+        if panels.is_empty() {
+            return;
+        }
+        let index = self.current_panel(panels.len());
+        let (slot, panel) = panels[index];
+        let Some(tex) = self.textures[slot].clone() else { return };
+        if self.panel_edit {
+            self.edit_panels(ui, rect, &tex);
+            return;
+        }
+
+        // Glide from the previous panel when it is on the same page
+        let mut uv = panel.to_uv();
+        if let Some((from_slot, from, start)) = self.panel_animation {
+            let duration = self.config.pan_animation_ms.max(1) as f32 / 1000.0;
+            let t = (start.elapsed().as_secs_f32() / duration).min(1.0);
+            if from_slot == slot && t < 1.0 {
+                let eased = t * t * (3.0 - 2.0 * t); // smoothstep
+                let from = from.to_uv();
+                uv = Rect::from_min_max(from.min.lerp(uv.min, eased), from.max.lerp(uv.max, eased));
+                ctx.request_repaint();
+            } else {
+                self.panel_animation = None;
+            }
+        }
+
+        let tex_size = tex.size_vec2();
+        let panel_size = uv.size() * tex_size;
+        let scale = (rect.width() / panel_size.x).min(rect.height() / panel_size.y) * PANEL_FILL;
+        let page_rect = Rect::from_min_size(rect.center() - uv.center().to_vec2() * tex_size * scale, tex_size * scale);
+        let panel_rect = Rect::from_min_max(page_rect.lerp_inside(uv.min.to_vec2()), page_rect.lerp_inside(uv.max.to_vec2()));
+
+        let painter = ui.painter_at(rect);
+        let full_uv = Rect::from_min_max(egui::Pos2::ZERO, egui::pos2(1.0, 1.0));
+        painter.image(tex.id(), page_rect, full_uv, egui::Color32::WHITE);
+        self.page_rects[slot] = Some(page_rect);
+        self.paint_tiles(&painter, slot, page_rect);
+        let dim = egui::Color32::from_black_alpha(PANEL_DIM_ALPHA);
+        for outside in [
+            Rect::from_min_max(rect.min, egui::pos2(rect.max.x, panel_rect.min.y)),
+            Rect::from_min_max(egui::pos2(rect.min.x, panel_rect.max.y), rect.max),
+            Rect::from_min_max(egui::pos2(rect.min.x, panel_rect.min.y), egui::pos2(panel_rect.min.x, panel_rect.max.y)),
+            Rect::from_min_max(egui::pos2(panel_rect.max.x, panel_rect.min.y), egui::pos2(rect.max.x, panel_rect.max.y)),
+        ] {
+            if outside.is_positive() {
+                painter.rect_filled(outside, 0.0, dim);
+            }
+        }
+
+        let resp = ui.interact(rect, ui.id().with("panel_hit"), egui::Sense::click());
+        if resp.clicked() {
+            self.panel_step(true, ctx);
+        }
+        if resp.clicked_by(PointerButton::Secondary) {
+            self.panel_step(false, ctx);
+        }
+    }
+
+    /// Whole page with its numbered panels: drag to draw a panel, right click one to remove it.
+    /// Changes are saved for the book right away.
+    fn edit_panels(&mut self, ui: &mut egui::Ui, rect: Rect, tex: &egui::TextureHandle) {
+        let tex_size = tex.size_vec2();
+        let scale = (rect.width() / tex_size.x).min(rect.height() / tex_size.y);
+        let page_rect = Rect::from_center_size(rect.center(), tex_size * scale);
+        let to_screen = |uv: Rect| Rect::from_min_max(page_rect.lerp_inside(uv.min.to_vec2()), page_rect.lerp_inside(uv.max.to_vec2()));
+        let to_uv = |pos: egui::Pos2| ((pos - page_rect.min) / page_rect.size()).to_pos2();
+
+        let key = tex.name();
+        let mut panels = self.page_panels(&key);
+        let painter = ui.painter_at(rect);
+        let full_uv = Rect::from_min_max(egui::Pos2::ZERO, egui::pos2(1.0, 1.0));
+        painter.image(tex.id(), page_rect, full_uv, egui::Color32::WHITE);
+        let outline = egui::Color32::from_rgb(255, 140, 0);
+        for (i, panel) in panels.iter().enumerate() {
+            let panel_rect = to_screen(panel.to_uv());
+            painter.rect_stroke(panel_rect, 0.0, egui::Stroke::new(2.0, outline), egui::StrokeKind::Inside);
+            painter.text(panel_rect.min + egui::vec2(6.0, 4.0), egui::Align2::LEFT_TOP, (i + 1).to_string(), egui::FontId::proportional(22.0), outline);
+        }
+
+        let resp = ui.interact(rect, ui.id().with("panel_edit"), egui::Sense::click_and_drag());
+        let mut changed = false;
+        if resp.drag_started_by(PointerButton::Primary) {
+            self.panel_drag_start = ui.input(|i| i.pointer.press_origin());
+        }
+        if let (Some(start), Some(end)) = (self.panel_drag_start, resp.interact_pointer_pos()) {
+            painter.rect_stroke(Rect::from_two_pos(start, end), 0.0, egui::Stroke::new(2.0, egui::Color32::YELLOW), egui::StrokeKind::Inside);
+            if resp.drag_stopped() {
+                self.panel_drag_start = None;
+                let drawn = PanelRect::from_uv(Rect::from_two_pos(to_uv(start), to_uv(end)));
+                if drawn.w * drawn.h >= MIN_DRAWN_PANEL_AREA {
+                    // The whole page fallback makes way for the first drawn panel
+                    panels.retain(|p| *p != PanelRect::FULL_PAGE);
+                    panels.push(drawn);
+                    let right_to_left = self.page_view() != PageViewOptions::DoubleLR;
+                    panels::sort_reading_order(&mut panels, right_to_left);
+                    changed = true;
+                }
+            }
+        }
+        if resp.clicked_by(PointerButton::Secondary) {
+            if let Some(i) = resp.interact_pointer_pos().and_then(|pos| panels::panel_at(&panels, to_uv(pos))) {
+                panels.remove(i);
+                changed = true;
+            }
+        }
+
+        if changed {
+            if let Some(source_path) = self.zip_path.clone() {
+                let crop = self.page_crops.get(&key).copied().unwrap_or_default();
+                let panels = panels.into_iter().map(|panel| panel.to_uncropped(crop)).collect();
+                self.book_settings.panels.insert(key, panels);
+                self.book_settings_store.set(&source_path, self.book_settings.clone());
+            }
+        }
+    }
+
+    /// Draw the full resolution tiles of an oversized page over its stand-in texture
+    fn paint_tiles(&mut self, painter: &egui::Painter, slot: usize, rect: Rect) {
+        let Some(name) = self.textures[slot].as_ref().map(|tex| tex.name()) else { return };
+        if let Some(tiled) = self.tiled_pages.get_mut(&name) {
+            tiled.paint(painter, rect, &name);
+        }
+    }
+
+    /// Free the tiles that went off screen, and the tiled pages no longer loaded
+    fn evict_tiles(&mut self) {
+        if self.tiled_pages.is_empty() {
+            return;
+        }
+        let loaded: Vec<String> = self.textures.iter()
+            .chain(self.buffer_next.iter())
+            .chain(self.buffer_prev.iter())
+            .flatten()
+            .map(|tex| tex.name())
+            .chain(self.texture_cache.keys().cloned())
+            .collect();
+        self.tiled_pages.retain(|name, _| loaded.contains(name));
+        for tiled in self.tiled_pages.values_mut() {
+            tiled.evict_unused();
+        }
+    }
+
+    /// Thumbnail of a page and the part of it to show, asking the worker for it when missing.
+    /// `None` for blank pages and thumbnails not done yet.
+    fn page_thumbnail(&mut self, page: PageEntry) -> Option<(egui::TextureHandle, Rect)> {
+        let file_index = page.file_index()?;
+        let name = self.image_files.get(file_index)?;
+        self.thumbnails.request(file_index, name);
+        let uv = match page {
+            PageEntry::Half(_, HalfSide::Left) => Rect::from_min_max(egui::Pos2::ZERO, egui::pos2(0.5, 1.0)),
+            PageEntry::Half(_, HalfSide::Right) => Rect::from_min_max(egui::pos2(0.5, 0.0), egui::pos2(1.0, 1.0)),
+            _ => Rect::from_min_max(egui::Pos2::ZERO, egui::pos2(1.0, 1.0)),
+        };
+        self.thumbnails.get(file_index).map(|tex| (tex.clone(), uv))
+    }
+
+    /// Popup below the page slider with the pages under its handle
+    fn show_scrub_preview(&mut self, ctx: &egui::Context, slider_rect: Rect, target: usize) {
+        // Keep the thumbnails around the handle coming
+        let prefetch_end = (target + SCRUB_PREFETCH + 2).min(self.pages.len());
+        for pos in target.saturating_sub(SCRUB_PREFETCH)..prefetch_end {
+            let _ = self.page_thumbnail(self.pages[pos]);
+        }
+
+        let visible = if self.is_single_page() || (self.is_shifted && target == 0) { 1 } else { 2 };
+        let mut pages: Vec<PageEntry> = self.pages.iter().skip(target).take(visible).copied().collect();
+        // Right to left books show the first page on the right
+        if self.page_view() != PageViewOptions::DoubleLR {
+            pages.reverse();
+        }
+        let thumbnails: Vec<(PageEntry, Option<(egui::TextureHandle, Rect)>, egui::Vec2)> = pages.into_iter()
+            .map(|page| {
+                let thumbnail = self.page_thumbnail(page);
+                let aspect = thumbnail.as_ref().map_or(0.7, |(tex, uv)| {
+                    let size = tex.size_vec2() * uv.size();
+                    size.x / size.y
+                });
+                (page, thumbnail, egui::vec2(SCRUB_PREVIEW_HEIGHT * aspect, SCRUB_PREVIEW_HEIGHT))
+            })
+            .collect();
+
+        let width: f32 = thumbnails.iter().map(|(_, _, size)| size.x).sum();
+        let x = ctx.pointer_interact_pos().map_or(slider_rect.center().x, |pos| pos.x);
+        let page_count = self.pages.len();
+        egui::Area::new(egui::Id::new("scrub_preview"))
+            .order(egui::Order::Tooltip)
+            .fixed_pos(egui::pos2(x - width / 2.0, slider_rect.bottom() + 8.0))
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.spacing_mut().item_spacing.x = 0.0;
+                        for (page, thumbnail, size) in &thumbnails {
+                            match thumbnail {
+                                Some((tex, uv)) => {
+                                    ui.add(egui::Image::new(tex).uv(*uv).fit_to_exact_size(*size));
+                                }
+                                None => {
+                                    // Blank pages are white, pages still decoding gray
+                                    let (rect, _) = ui.allocate_exact_size(*size, egui::Sense::hover());
+                                    let fill = if *page == PageEntry::Blank { egui::Color32::WHITE } else { egui::Color32::from_gray(60) };
+                                    ui.painter().rect_filled(rect, 0.0, fill);
+                                }
+                            }
+                        }
+                    });
+                    ui.vertical_centered(|ui| {
+                        ui.label(egui::RichText::new(format!("{} / {}", target + 1, page_count)).strong());
+                    });
+                });
+            });
+    }
+
+    fn set_page_overview(&mut self, enabled: bool) {
+        self.page_overview = enabled && !self.pages.is_empty();
+        if self.page_overview {
+            let spreads = self.overview_spreads();
+            self.overview_selected = spreads[Self::spread_of(&spreads, self.current_index)];
+            self.overview_scroll = true;
+        }
+    }
+
+    /// First page of every screen of the book, the way they are turned
+    fn overview_spreads(&self) -> Vec<usize> {
+        if self.is_single_page() {
+            return (0..self.pages.len()).collect();
+        }
+        // In the shifted mode the first page stands alone
+        let first_pair = if self.is_shifted { 1.min(self.pages.len()) } else { 0 };
+        (0..first_pair).chain((first_pair..self.pages.len()).step_by(2)).collect()
+    }
+
+    /// Position in `spreads` of the spread that shows `page`
+    fn spread_of(spreads: &[usize], page: usize) -> usize {
+        spreads.partition_point(|&start| start <= page).saturating_sub(1)
+    }
+
+    fn go_to_page(&mut self, page: usize, ctx: &egui::Context) {
+        if page < self.pages.len() && page != self.current_index {
+            self.reset_buffer();
+            self.current_index = page;
+            self.textures = self.load_pair_progressive(self.current_index, ctx);
+            self.page_indicator_time = Some(Instant::now());
+        }
+    }
+
+    /// Full screen grid of the spreads of the book. Right to left books fill the rows from the right.
+    fn show_page_overview(&mut self, ui: &mut egui::Ui, rect: Rect, ctx: &egui::Context) {
+        let spreads = self.overview_spreads();
+        if spreads.is_empty() {
+            return;
+        }
+        let right_to_left = self.page_view() == PageViewOptions::DoubleRL;
+        let slots = if self.is_single_page() { 1.0 } else { 2.0 };
+        let spacing = ui.spacing().item_spacing;
+        let cell_size = egui::vec2(OVERVIEW_THUMB_HEIGHT * OVERVIEW_PAGE_ASPECT * slots + 16.0, OVERVIEW_THUMB_HEIGHT + 28.0);
+        let usable_width = rect.width() - ui.spacing().scroll.bar_width - 2.0 * spacing.x;
+        let columns = (((usable_width + spacing.x) / (cell_size.x + spacing.x)).floor() as usize).max(1);
+        let rows = spreads.len().div_ceil(columns);
+        let current = Self::spread_of(&spreads, self.current_index);
+        let mut selected = Self::spread_of(&spreads, self.overview_selected);
+
+        // Keyboard: arrows move the selection, Enter opens it, Escape closes the grid
+        let (forward, backward) = if right_to_left {
+            (egui::Key::ArrowLeft, egui::Key::ArrowRight)
+        } else {
+            (egui::Key::ArrowRight, egui::Key::ArrowLeft)
+        };
+        let mut open = None;
+        let before = selected;
+        ctx.input(|i| {
+            if i.key_pressed(forward) { selected = (selected + 1).min(spreads.len() - 1); }
+            if i.key_pressed(backward) { selected = selected.saturating_sub(1); }
+            if i.key_pressed(egui::Key::ArrowDown) { selected = (selected + columns).min(spreads.len() - 1); }
+            if i.key_pressed(egui::Key::ArrowUp) { selected = selected.saturating_sub(columns); }
+            if i.key_pressed(egui::Key::Home) { selected = 0; }
+            if i.key_pressed(egui::Key::End) { selected = spreads.len() - 1; }
+            if i.key_pressed(egui::Key::Enter) { open = Some(selected); }
+        });
+        if ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+            self.page_overview = false;
+            return;
+        }
+        if selected != before {
+            self.overview_scroll = true;
+        }
+
+        let row_height = cell_size.y + spacing.y;
+        let mut scroll = egui::ScrollArea::vertical().auto_shrink(false);
+        if std::mem::take(&mut self.overview_scroll) {
+            // Keep the selected row in the middle of the screen
+            let offset = (selected / columns) as f32 * row_height - (rect.height() - row_height) / 2.0;
+            scroll = scroll.vertical_scroll_offset(offset.max(0.0));
+        }
+
+        let mut child = ui.new_child(egui::UiBuilder::new().max_rect(rect));
+        child.add_space(spacing.y);
+        scroll.show_rows(&mut child, cell_size.y, rows, |ui, row_range| {
+            for row in row_range {
+                ui.horizontal(|ui| {
+                    ui.add_space(spacing.x);
+                    let in_row = (spreads.len() - row * columns).min(columns);
+                    for column in 0..columns {
+                        // Right to left rows start at the right edge
+                        let column = if right_to_left { columns - 1 - column } else { column };
+                        if column >= in_row {
+                            ui.allocate_exact_size(cell_size, egui::Sense::hover());
+                            continue;
+                        }
+                        let index = row * columns + column;
+                        let (cell, response) = ui.allocate_exact_size(cell_size, egui::Sense::click());
+                        if response.clicked() {
+                            open = Some(index);
+                        }
+                        // Only a moving mouse takes the selection from the keyboard
+                        if response.hovered() && ui.input(|i| i.pointer.delta() != egui::Vec2::ZERO) {
+                            selected = index;
+                        }
+                        self.paint_overview_cell(ui, cell, &spreads, index, index == current, index == selected || response.hovered());
+                    }
+                });
+            }
+        });
+        self.overview_selected = spreads[selected];
+
+        if let Some(index) = open {
+            self.page_overview = false;
+            self.go_to_page(spreads[index], ctx);
+        }
+    }
+
+    /// One spread of the overview grid, its pages side by side as they are shown when reading
+    fn paint_overview_cell(&mut self, ui: &egui::Ui, cell: Rect, spreads: &[usize], index: usize, is_current: bool, is_selected: bool) {
+        let start = spreads[index];
+        let end = spreads.get(index + 1).copied().unwrap_or(self.pages.len());
+        let mut pages: Vec<PageEntry> = self.pages[start..end].to_vec();
+        if self.page_view() == PageViewOptions::DoubleRL {
+            pages.reverse();
+        }
+        let bookmarked = self.bookmark_positions().into_iter().any(|pos| (start..end).contains(&pos));
+        let visuals = ui.visuals();
+        let painter = ui.painter();
+        if is_selected {
+            painter.rect_filled(cell, 6.0, visuals.widgets.hovered.weak_bg_fill);
+        }
+
+        let slot_width = OVERVIEW_THUMB_HEIGHT * OVERVIEW_PAGE_ASPECT;
+        let thumbnails: Vec<(PageEntry, Option<(egui::TextureHandle, Rect)>, egui::Vec2)> = pages.into_iter()
+            .map(|page| {
+                let thumbnail = self.page_thumbnail(page);
+                let size = thumbnail.as_ref().map_or(egui::vec2(slot_width, OVERVIEW_THUMB_HEIGHT), |(tex, uv)| {
+                    let size = tex.size_vec2() * uv.size();
+                    // Wide pages may take the whole cell
+                    size * (OVERVIEW_THUMB_HEIGHT / size.y).min((cell.width() - 16.0) / size.x)
+                });
+                (page, thumbnail, size)
+            })
+            .collect();
+
+        let width: f32 = thumbnails.iter().map(|(_, _, size)| size.x).sum();
+        let mut x = cell.center().x - width / 2.0;
+        let top = cell.top() + 6.0;
+        let mut spread_rect = Rect::NOTHING;
+        for (page, thumbnail, size) in thumbnails {
+            let page_rect = Rect::from_min_size(egui::pos2(x, top + (OVERVIEW_THUMB_HEIGHT - size.y) / 2.0), size);
+            match thumbnail {
+                Some((tex, uv)) => {
+                    painter.image(tex.id(), page_rect, uv, egui::Color32::WHITE);
+                }
+                None => {
+                    // Blank pages are white, pages still decoding gray
+                    let fill = if page == PageEntry::Blank { egui::Color32::WHITE } else { egui::Color32::from_gray(60) };
+                    painter.rect_filled(page_rect, 0.0, fill);
+                }
+            }
+            spread_rect = spread_rect.union(page_rect);
+            x += size.x;
+        }
+
+        if is_current {
+            painter.rect_stroke(spread_rect.expand(2.0), 2.0, egui::Stroke::new(3.0, visuals.selection.bg_fill), egui::StrokeKind::Outside);
+        } else if is_selected {
+            painter.rect_stroke(spread_rect.expand(2.0), 2.0, egui::Stroke::new(2.0, visuals.widgets.hovered.fg_stroke.color), egui::StrokeKind::Outside);
+        }
+
+        if bookmarked {
+            // Ribbon in the top right corner of the spread
+            let corner = spread_rect.right_top() + egui::vec2(-14.0, 0.0);
+            painter.add(egui::Shape::convex_polygon(
+                vec![corner, corner + egui::vec2(10.0, 0.0), corner + egui::vec2(10.0, 18.0), corner + egui::vec2(5.0, 13.0), corner + egui::vec2(0.0, 18.0)],
+                BOOKMARK_COLOR,
+                egui::Stroke::NONE,
+            ));
+        }
+
+        let label = if end - start > 1 { format!("{}-{}", start + 1, end) } else { format!("{}", start + 1) };
+        painter.text(
+            egui::pos2(cell.center().x, cell.bottom() - 4.0),
+            egui::Align2::CENTER_BOTTOM,
+            label,
+            egui::FontId::proportional(14.0),
+            if is_current { visuals.strong_text_color() } else { visuals.text_color() },
+        );
+    }
+
+    /// Write the page and layout of the open book to the history when they changed
+    fn update_history(&mut self) {
+        let Some(book_id) = &self.book_id else { return };
+        let state = (self.current_index, self.is_shifted, self.view_mode);
+        if self.history_state == Some(state) || self.pages.is_empty() {
+            return;
+        }
+        self.history_state = Some(state);
+
+        let visible = if self.is_single_page() || (self.is_shifted && self.current_index == 0) { 1 } else { 2 };
+        let now = now_secs();
+        let history = self.book_db.record_mut(book_id).history.get_or_insert_with(|| ReadingHistory { first_opened: now, ..Default::default() });
+        history.last_page = self.current_index;
+        history.page_count = self.pages.len();
+        history.is_shifted = self.is_shifted;
+        history.view_mode = self.view_mode;
+        history.last_read = now;
+        if self.current_index + visible >= self.pages.len() {
+            history.finished = true;
+        }
+
+        if let Some(recent) = self.config.recent_files.first_mut().filter(|recent| Some(&recent.path) == self.zip_path.as_ref()) {
+            recent.page = self.current_index;
+            recent.page_count = self.pages.len();
+        }
+    }
+
+    /// Put a source at the top of the recent files
+    fn add_recent_file(&mut self, path: &Path) {
+        let mut recent_files = std::mem::take(&mut self.config.recent_files);
+        let previous = recent_files.iter().position(|recent| recent.path == path).map(|i| recent_files.remove(i));
+        recent_files.insert(0, previous.unwrap_or(RecentFile { path: path.to_path_buf(), page: 0, page_count: 0 }));
+        recent_files.retain(|recent| recent.path.exists());
+        recent_files.truncate(self.config.recent_files_max);
+        self.config.recent_files = recent_files;
+        // Keeps the page the previous book was left at
+        self.save_settings();
+    }
+
+    /// Open the most recent source other than the one on screen
+    fn reopen_last(&mut self, ctx: &egui::Context) {
+        let last = self.config.recent_files.iter()
+            .find(|recent| Some(&recent.path) != self.zip_path.as_ref())
+            .map(|recent| recent.path.clone());
+        match last {
+            Some(path) if path.exists() => self.load_source(path, ctx),
+            Some(_) => {
+                self.config.recent_files.retain(|recent| recent.path.exists());
+                self.show_fading_error("The last file was moved or deleted.");
+            }
+            None => self.show_fading_error("No recent files."),
+        }
+    }
+
+    /// Buttons for the recent files, returns the one clicked
+    fn recent_files_list(&self, ui: &mut egui::Ui) -> Option<PathBuf> {
+        let mut clicked = None;
+        for recent in &self.config.recent_files {
+            let name = ParsedName::display_name(&recent.path);
+            let text = if recent.page_count > 0 {
+                format!("{}   ({} / {})", name, recent.page + 1, recent.page_count)
+            } else {
+                name
+            };
+            if ui.button(text).on_hover_text(recent.path.to_string_lossy()).clicked() {
+                clicked = Some(recent.path.clone());
+            }
+        }
+        clicked
+    }
+
+    /// Order of the files for all folders, and of the folder of the open book and its pages
+    fn file_order_ui(&mut self, ui: &mut egui::Ui, ctx: &egui::Context) {
+        fn sort_order_ui(ui: &mut egui::Ui, id: &str, order: &mut SortOrder) -> bool {
+            let mut changed = false;
+            ui.horizontal(|ui| {
+                egui::ComboBox::from_id_salt(id)
+                    .selected_text(order.key.label())
+                    .show_ui(ui, |ui| {
+                        for key in SortKey::ALL {
+                            changed |= ui.selectable_value(&mut order.key, key, key.label()).changed();
+                        }
+                    });
+                changed |= ui.checkbox(&mut order.descending, "Descending").changed();
+            });
+            changed
+        }
+
+        ui.label("All folders:");
+        let mut changed = sort_order_ui(ui, "file_sort_default", &mut self.config.file_sorting.default);
+
+        let mut folders = Vec::new();
+        if let Some(source) = &self.zip_path {
+            if let Some(parent) = source.parent() {
+                folders.push(("Own order for the files in this folder", parent.to_path_buf()));
+            }
+            if self.source_mode == SourceMode::Folder {
+                folders.push(("Own order for the pages of this book", source.clone()));
+            }
+        }
+        for (label, folder) in folders {
+            let key = folder.to_string_lossy().to_string();
+            let mut own = self.config.file_sorting.folders.contains_key(&key);
+            if ui.checkbox(&mut own, label).on_hover_text(&key).changed() {
+                if own {
+                    self.config.file_sorting.folders.insert(key.clone(), self.config.file_sorting.default);
+                } else {
+                    self.config.file_sorting.folders.remove(&key);
+                }
+                changed = true;
+            }
+            if let Some(order) = self.config.file_sorting.folders.get_mut(&key) {
+                changed |= sort_order_ui(ui, &key, order);
+            }
+        }
+
+        if changed {
+            self.save_settings();
+            self.source_walk = None;
+            let Some(source) = self.zip_path.clone() else { return };
+            self.all_zips_in_folder = self.scan_folder(source.parent().unwrap_or(Path::new("")), ctx);
+            if self.source_mode == SourceMode::Folder {
+                // Stay at the image on screen, the pages may have moved around it
+                self.refresh_folder_pages(&[], ctx);
+            }
+        }
+    }
+
+    /// Status, rating and tags of the open book in one line, `None` when there is nothing to tell
+    fn book_badge(&self) -> Option<String> {
+        let record = self.book_id.as_ref().and_then(|id| self.book_db.get(id))?;
+        let mut badge = record.read_status().label().to_string();
+        if let Some(rating) = record.rating {
+            // A hand edited database may hold more than five stars
+            let stars = rating.min(5) as usize;
+            badge += &format!("   {}{}", "★".repeat(stars), "☆".repeat(5 - stars));
+        }
+        for tag in &record.tags {
+            badge += &format!("   #{}", tag);
+        }
+        Some(badge)
+    }
+
+    /// Status, rating and tags of the open book, for the settings panel
+    fn book_info_ui(&mut self, ui: &mut egui::Ui) {
+        let Some(book_id) = self.book_id.clone() else { return };
+        // Edited on a copy, the record is only touched when something really changed
+        let (mut status, mut rating, mut tags) = self.book_db.get(&book_id)
+            .map_or((None, None, Vec::new()), |record| (record.status, record.rating, record.tags.clone()));
+        let mut changed = false;
+
+        let name = ParsedName::of_path(Path::new(&book_id.path));
+        if name.is_numbered() {
+            ui.label(name.display());
+            if let Some(group) = &name.group {
+                ui.weak(format!("Group: {}", group));
+            }
+        }
+
+        let status_text = |status: Option<ReadStatus>| status.map_or("Automatic", |s| s.label());
+        egui::ComboBox::from_label("Status")
+            .selected_text(status_text(status))
+            .show_ui(ui, |ui| {
+                for value in [None, Some(ReadStatus::Unread), Some(ReadStatus::InProgress), Some(ReadStatus::Finished)] {
+                    changed |= ui.selectable_value(&mut status, value, status_text(value)).changed();
+                }
+            })
+            .response
+            .on_hover_text("Automatic follows the reading: unread until opened, finished once the last page was shown.");
+
+        ui.horizontal(|ui| {
+            ui.label("Rating:");
+            for stars in 1..=5u8 {
+                let filled = rating.is_some_and(|r| r >= stars);
+                if ui.selectable_label(false, if filled { "★" } else { "☆" }).clicked() {
+                    // Clicking the current rating clears it
+                    rating = if rating == Some(stars) { None } else { Some(stars) };
+                    changed = true;
+                }
+            }
+        });
+
+        ui.horizontal_wrapped(|ui| {
+            ui.label("Tags:");
+            let mut removed = None;
+            for (i, tag) in tags.iter().enumerate() {
+                if ui.small_button(format!("{} ❌", tag)).on_hover_text("Remove Tag").clicked() {
+                    removed = Some(i);
+                }
+            }
+            if let Some(i) = removed {
+                tags.remove(i);
+                changed = true;
+            }
+            let input = ui.add(egui::TextEdit::singleline(&mut self.tag_input).hint_text("Add tag").desired_width(100.0));
+            if input.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                let tag = self.tag_input.trim().to_string();
+                if !tag.is_empty() && !tags.contains(&tag) {
+                    tags.push(tag);
+                    changed = true;
+                }
+                self.tag_input.clear();
+            }
+        });
+
+        if changed {
+            let record = self.book_db.record_mut(&book_id);
+            record.status = status;
+            record.rating = rating;
+            record.tags = tags;
+            self.book_db.save();
+        }
+    }
+
+    fn bookmarks(&self) -> &[Bookmark] {
+        self.book_id.as_ref()
+            .and_then(|id| self.book_db.get(id))
+            .map_or(&[], |record| record.bookmarks.as_slice())
+    }
+
+    /// Find the bookmarked images in a new or changed image list
+    fn locate_bookmarks(&mut self) {
+        let Some(book_id) = self.book_id.clone() else { return };
+        if self.book_db.get(&book_id).is_some_and(|record| !record.bookmarks.is_empty()) {
+            self.book_db.record_mut(&book_id).locate_bookmarks(&self.image_files);
+        }
+    }
+
+    /// Position in `pages` of each bookmark, in page order
+    fn bookmark_positions(&self) -> Vec<usize> {
+        self.bookmarks().iter()
+            .filter_map(|bookmark| bookmark.file_index)
+            .filter_map(|file_index| self.pages.iter().position(|p| p.file_index() == Some(file_index)))
+            .collect()
+    }
+
+    /// Image shown first on screen, the one a new bookmark marks
+    fn visible_file_index(&self) -> Option<usize> {
+        let visible = if self.is_single_page() || (self.is_shifted && self.current_index == 0) { 1 } else { 2 };
+        self.pages.iter().skip(self.current_index).take(visible).find_map(|p| p.file_index())
+    }
+
+    fn toggle_bookmark(&mut self) {
+        let (Some(book_id), Some(file_index)) = (self.book_id.clone(), self.visible_file_index()) else { return };
+        let added = self.book_db.record_mut(&book_id).toggle_bookmark(file_index, &self.image_files);
+        self.book_db.save();
+        self.show_fading_error(if added { "Bookmark added" } else { "Bookmark removed" });
+    }
+
+    /// Go to the spread of the next or previous bookmark
+    fn bookmark_step(&mut self, forward: bool, ctx: &egui::Context) {
+        let spreads = self.overview_spreads();
+        let current = Self::spread_of(&spreads, self.current_index);
+        let bookmarked: Vec<usize> = self.bookmark_positions().into_iter().map(|pos| Self::spread_of(&spreads, pos)).collect();
+        let target = if forward {
+            bookmarked.iter().copied().filter(|&s| s > current).min()
+        } else {
+            bookmarked.iter().copied().filter(|&s| s < current).max()
+        };
+        match target {
+            Some(spread) => self.go_to_page(spreads[spread], ctx),
+            None if bookmarked.is_empty() => self.show_fading_error("No bookmarks in this book"),
+            None => self.show_fading_error(if forward { "No more bookmarks" } else { "No earlier bookmarks" }),
+        }
+    }
+
+    /// Marks on the page slider where the bookmarks are. `rail` is the slider without its value box.
+    fn paint_bookmark_ticks(&self, painter: &egui::Painter, rail: Rect) {
+        let page_count = self.pages.len();
+        if page_count < 2 {
+            return;
+        }
+        // Same inset as the slider handle, so the marks line up with it
+        let handle_radius = rail.height() / 2.5;
+        let range = rail.x_range().shrink(handle_radius);
+        for pos in self.bookmark_positions() {
+            let x = egui::lerp(range.min..=range.max, pos as f32 / (page_count - 1) as f32);
+            painter.line_segment(
+                [egui::pos2(x, rail.top() + 2.0), egui::pos2(x, rail.top() + rail.height() * 0.3)],
+                egui::Stroke::new(2.0, BOOKMARK_COLOR),
+            );
+        }
+    }
+
+    fn export_bookmarks(&mut self, path: &Path) {
+        let Some(record) = self.book_id.as_ref().and_then(|id| self.book_db.get(id)) else { return };
+        let json = record.export_bookmarks(&self.image_files);
+        if std::fs::write(path, json).is_err() {
+            self.show_fading_error("Could not write the bookmarks file.");
+        }
+    }
+
+    fn import_bookmarks(&mut self, path: &Path) {
+        let Some(book_id) = self.book_id.clone() else { return };
+        let Ok(json) = std::fs::read_to_string(path) else {
+            self.show_fading_error("Could not read the bookmarks file.");
+            return;
+        };
+        match self.book_db.record_mut(&book_id).import_bookmarks(&json, &self.image_files) {
+            Ok(added) => {
+                self.book_db.save();
+                self.show_fading_error(&format!("Imported {} bookmarks", added));
+            }
+            Err(_) => self.show_fading_error("Not a bookmarks file."),
+        }
+    }
+
+    /// Window listing the bookmarks of the open book, with their labels
+    fn show_bookmark_list(&mut self, ctx: &egui::Context) {
+        if !self.show_bookmarks {
+            return;
+        }
+        let Some(book_id) = self.book_id.clone() else { return };
+        let mut open = true;
+        let mut go_to = None;
+        let mut remove = None;
+        let mut save = false;
+        let positions: Vec<Option<usize>> = self.bookmarks().iter()
+            .map(|bookmark| bookmark.file_index.and_then(|file_index| self.pages.iter().position(|p| p.file_index() == Some(file_index))))
+            .collect();
+        // Edited here, the record is only touched when a label really changed
+        let mut labels: Vec<String> = self.bookmarks().iter().map(|bookmark| bookmark.label.clone()).collect();
+        let mut relabeled = None;
+
+        egui::Window::new("Bookmarks")
+            .open(&mut open)
+            .default_width(300.0)
+            .show(ctx, |ui| {
+                if labels.is_empty() {
+                    ui.label("No bookmarks yet.");
+                }
+                egui::ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+                    for (i, label) in labels.iter_mut().enumerate() {
+                        ui.horizontal(|ui| {
+                            let page = positions[i].map_or("-".to_string(), |pos| (pos + 1).to_string());
+                            if ui.button(format!("p. {}", page)).on_hover_text("Go to Bookmark").clicked() {
+                                go_to = positions[i];
+                            }
+                            let edit = ui.add(egui::TextEdit::singleline(label).hint_text("Label").desired_width(160.0));
+                            if edit.changed() {
+                                relabeled = Some(i);
+                            }
+                            save |= edit.lost_focus();
+                            if ui.small_button("❌").on_hover_text("Remove Bookmark").clicked() {
+                                remove = Some(i);
+                            }
+                        });
+                    }
+                });
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui.button("Export...").clicked() {
+                        self.pick_path(PickPurpose::ExportBookmarks);
+                    }
+                    if ui.button("Import...").clicked() {
+                        self.pick_path(PickPurpose::ImportBookmarks);
+                    }
+                });
+            });
+
+        if let Some(i) = relabeled {
+            if let Some(bookmark) = self.book_db.record_mut(&book_id).bookmarks.get_mut(i) {
+                bookmark.label = std::mem::take(&mut labels[i]);
+            }
+        }
+        if let Some(i) = remove {
+            self.book_db.record_mut(&book_id).bookmarks.remove(i);
+            save = true;
+        }
+        if save {
+            self.book_db.save();
+        }
+        if let Some(pos) = go_to {
+            let spreads = self.overview_spreads();
+            self.go_to_page(spreads[Self::spread_of(&spreads, pos)], ctx);
+        }
+        self.show_bookmarks = open;
+    }
+
+    /// Put back the page, zoom and fullscreen state of the last session
+    fn restore_session(&mut self, session: Session, ctx: &egui::Context) {
+        if !self.config.restore_session {
+            return;
+        }
+        if session.fullscreen {
+            self.is_fullscreen = true;
+            ctx.send_viewport_cmd(egui::ViewportCommand::Fullscreen(true));
+        }
+        if self.zip_path.is_none() || self.zip_path != session.book {
+            return;
+        }
+
+        self.is_shifted = session.is_shifted;
+        if session.view_mode != self.view_mode {
+            self.view_mode = session.view_mode;
+            self.rebuild_pages(ctx);
+        }
+        let page = session.page.min(self.pages.len().saturating_sub(1));
+        if page != self.current_index {
+            self.current_index = page;
+            self.reset_buffer();
+            self.textures = self.load_pair(self.current_index, ctx);
+        }
+        // The session already is where the reading stopped
+        self.resume_prompt = None;
+        if session.zoom != 1.0 {
+            self.zoom_factor = session.zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+            self.pending_scroll_offset = Some(egui::Vec2::from(session.scroll_offset));
+            self.zoom_changed_time = Some(Instant::now());
+        }
+    }
+
+    /// Remember the window geometry, and write the session and the reading history after navigating
+    fn update_session(&mut self, ctx: &egui::Context) {
+        let (outer, inner, maximized, fullscreen) = ctx.input(|i| {
+            let viewport = i.viewport();
+            (viewport.outer_rect, viewport.inner_rect, viewport.maximized.unwrap_or(false), viewport.fullscreen.unwrap_or(false))
+        });
+        if !fullscreen {
+            self.window_maximized = maximized;
+            if let (false, Some(outer), Some(inner)) = (maximized, outer, inner) {
+                self.window_geometry = Some(([outer.min.x, outer.min.y], [inner.width(), inner.height()]));
+            }
+        }
+
+        // The reading history goes to disk with the session, a killed process keeps both
+        let state = (self.zip_path.clone(), self.current_index, self.is_shifted, self.view_mode);
+        if self.session_state.as_ref() != Some(&state) {
+            self.session_state = Some(state);
+            self.book_db.save();
+            self.save_session();
+        }
+    }
+
+    fn save_session(&self) {
+        if !self.config.restore_session {
+            return;
+        }
+        Session {
+            book: self.zip_path.clone(),
+            page: self.current_index,
+            is_shifted: self.is_shifted,
+            view_mode: self.view_mode,
+            zoom: self.zoom_factor,
+            scroll_offset: self.scroll_offset.into(),
+            fullscreen: self.is_fullscreen,
+            maximized: self.window_maximized,
+            window_pos: self.window_geometry.map(|(pos, _)| pos),
+            window_size: self.window_geometry.map(|(_, size)| size),
+        }.save();
+    }
+
+    /// Offer to start over after resuming, or to resume when the book opened at its start
+    fn show_resume_prompt(&mut self, ctx: &egui::Context) {
+        let Some((last_page, shown_at)) = self.resume_prompt else { return };
+        let resumed = self.config.resume_mode == ResumeMode::Resume;
+        if resumed && shown_at.elapsed() > RESUME_NOTICE_TIME {
+            self.resume_prompt = None;
+            return;
+        }
+        if resumed {
+            ctx.request_repaint_after(RESUME_NOTICE_TIME.saturating_sub(shown_at.elapsed()));
+        }
+
+        egui::Area::new(egui::Id::new("resume_prompt"))
+            .anchor(egui::Align2::CENTER_BOTTOM, [0.0, -60.0])
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        if resumed {
+                            ui.label(format!("Resumed at page {}", last_page + 1));
+                        } else {
+                            ui.label(format!("Last read at page {}", last_page + 1));
+                            if ui.button("Resume").clicked() {
+                                self.resume_prompt = None;
+                                self.go_to_page(last_page, ctx);
+                            }
+                        }
+                        if ui.button("Start Over").clicked() {
+                            self.resume_prompt = None;
+                            self.go_to_page(0, ctx);
+                        }
+                        if ui.small_button("❌").clicked() {
+                            self.resume_prompt = None;
+                        }
+                    });
+                });
+            });
+    }
+
+    /// Go back to the start screen, letting go of every file of the book
+    fn close_source(&mut self) {
+        self.book_db.save();
+        self.zip_path = None;
+        self.book_id = None;
+        self.view_mode = None;
+        self.image_files.clear();
+        self.pages.clear();
+        self.spread_pages.clear();
+        self.spread_scan = None;
+        self.all_zips_in_folder.clear();
+        self.folder_watcher = None;
+        self.thumbnails.clear();
+        self.reset_buffer();
+        self.textures = [None, None];
+        self.texture_cache.clear();
+        self.page_overview = false;
+        self.resume_prompt = None;
+    }
+
+    /// Book after the open one in its folder, or the one before when it was the last
+    fn neighbour_source(&self) -> Option<PathBuf> {
+        let current = self.zip_path.as_ref()?;
+        let pos = self.all_zips_in_folder.iter().position(|p| p == current)?;
+        self.all_zips_in_folder.get(pos + 1)
+            .or_else(|| pos.checked_sub(1).and_then(|i| self.all_zips_in_folder.get(i)))
+            .cloned()
+    }
+
+    /// Leave the open book for the next one of its folder, then run `action` on it.
+    /// The book is closed first, Windows refuses to move files that are open or watched.
+    fn curate(&mut self, ctx: &egui::Context, action: impl FnOnce(&Path) -> Result<Curation, String>) {
+        let Some(source) = self.zip_path.clone() else { return };
+        match self.neighbour_source() {
+            Some(next) => self.load_source(next, ctx),
+            None => self.close_source(),
+        }
+
+        match action(&source) {
+            Ok(curation) => {
+                if let Curation::Moved { from, to } = &curation {
+                    self.book_moved(from, to);
+                }
+                self.source_walk = None;
+                if let Some(parent) = self.zip_path.as_ref().and_then(|p| p.parent()).map(Path::to_path_buf) {
+                    self.all_zips_in_folder = self.scan_folder(&parent, ctx);
+                }
+                self.last_curation = Some(curation);
+                self.curation_notice = Some(Instant::now());
+            }
+            Err(e) => {
+                self.load_source(source, ctx);
+                self.show_fading_error(&e);
+            }
+        }
+    }
+
+    /// Everything remembered under the old path of a moved or renamed book goes to the new one
+    fn book_moved(&mut self, from: &Path, to: &Path) {
+        self.book_db.move_record(&BookId::of(from), &BookId::of(to));
+        self.book_db.save();
+        self.book_settings_store.move_entry(from, to);
+        self.book_settings_store.save();
+        for recent in self.config.recent_files.iter_mut().filter(|recent| recent.path == from) {
+            recent.path = to.to_path_buf();
+        }
+        self.save_settings();
+        // The session file may still name the old path
+        self.save_session();
+    }
+
+    fn open_target_picker(&mut self, picker: TargetPicker) {
+        if self.zip_path.is_some() {
+            self.target_picker = Some(picker);
+        }
+    }
+
+    /// Move or copy the open book into the target folder at `index`
+    fn send_to_target(&mut self, picker: TargetPicker, index: usize, ctx: &egui::Context) {
+        let Some(target) = self.config.curation_targets.get(index).cloned() else { return };
+        self.curate(ctx, |source| {
+            let dest = target.join(source.file_name().unwrap_or_default());
+            match picker {
+                TargetPicker::Move => curation::move_book(source, &dest)
+                    .map(|_| Curation::Moved { from: source.to_path_buf(), to: dest })
+                    .map_err(|e| format!("Move failed: {}", e)),
+                TargetPicker::Copy => curation::copy_book(source, &dest)
+                    .map(|_| Curation::Copied { original: source.to_path_buf(), copy: dest })
+                    .map_err(|e| format!("Copy failed: {}", e)),
+            }
+        });
+    }
+
+    fn trash_book(&mut self, ctx: &egui::Context) {
+        self.curate(ctx, |source| {
+            curation::trash_book(source)
+                .map(|_| Curation::Trashed { original: source.to_path_buf() })
+                .map_err(|e| format!("Trash failed: {}", e))
+        });
+    }
+
+    fn rename_book(&mut self, new_name: &str, ctx: &egui::Context) {
+        let new_name = new_name.trim();
+        let Some(source) = self.zip_path.clone() else { return };
+        if new_name.is_empty() || source.file_name().is_some_and(|name| name == new_name) {
+            return;
+        }
+        if new_name.contains(['/', '\\']) {
+            self.show_fading_error("A name can't contain a folder separator.");
+            return;
+        }
+        let dest = source.with_file_name(new_name);
+        self.curate(ctx, |source| {
+            curation::move_book(source, &dest)
+                .map(|_| Curation::Moved { from: source.to_path_buf(), to: dest })
+                .map_err(|e| format!("Rename failed: {}", e))
+        });
+    }
+
+    /// Take back the last move, copy, trash or rename and open the book again
+    fn undo_curation(&mut self, ctx: &egui::Context) {
+        let Some(curation) = self.last_curation.take() else {
+            self.show_fading_error("Nothing to undo.");
+            return;
+        };
+        self.curation_notice = None;
+        let moved_to = match &curation {
+            Curation::Moved { to, .. } => Some(to.clone()),
+            _ => None,
+        };
+        // The book or its copy may be open again by now
+        let in_use = match &curation {
+            Curation::Moved { to, .. } => Some(to),
+            Curation::Copied { copy, .. } => Some(copy),
+            Curation::Trashed { .. } => None,
+        };
+        if in_use.is_some() && self.zip_path.as_ref() == in_use {
+            self.close_source();
+        }
+
+        match curation.undo() {
+            Ok(()) => {
+                let original = curation.original().to_path_buf();
+                if let Some(moved_to) = moved_to {
+                    self.book_moved(&moved_to, &original);
+                }
+                self.load_source(original, ctx);
+            }
+            Err(e) => {
+                self.show_fading_error(&format!("Undo failed: {}", e));
+                self.last_curation = Some(curation);
+            }
+        }
+    }
+
+    /// Numbered list of the target folders, picked with a click or the number keys
+    fn show_target_picker(&mut self, ctx: &egui::Context) {
+        let Some(picker) = self.target_picker else { return };
+        const NUMBER_KEYS: [egui::Key; 9] = [
+            egui::Key::Num1, egui::Key::Num2, egui::Key::Num3, egui::Key::Num4, egui::Key::Num5,
+            egui::Key::Num6, egui::Key::Num7, egui::Key::Num8, egui::Key::Num9,
+        ];
+        let mut open = !ctx.input(|i| i.key_pressed(egui::Key::Escape));
+        let mut chosen = ctx.input(|i| NUMBER_KEYS.iter().position(|key| i.key_pressed(*key)));
+        let mut add_target = false;
+
+        let title = if picker == TargetPicker::Move { "Move Book To" } else { "Copy Book To" };
+        egui::Window::new(title)
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                if self.config.curation_targets.is_empty() {
+                    ui.label("No target folders yet.");
+                }
+                for (i, target) in self.config.curation_targets.iter().enumerate() {
+                    if ui.button(format!("{}  {}", i + 1, target.to_string_lossy())).clicked() {
+                        chosen = Some(i);
+                    }
+                }
+                ui.separator();
+                if ui.button("Add Folder").clicked() {
+                    add_target = true;
+                }
+            });
+
+        if add_target {
+            self.pick_path(PickPurpose::CurationTarget);
+        }
+        if let Some(index) = chosen.filter(|&i| i < self.config.curation_targets.len()) {
+            self.target_picker = None;
+            self.send_to_target(picker, index, ctx);
+        } else if !open {
+            self.target_picker = None;
+        }
+    }
+
+    fn show_rename_box(&mut self, ctx: &egui::Context) {
+        let Some(name) = self.rename_input.as_mut() else { return };
+        let mut confirm = false;
+        let mut cancel = ctx.input(|i| i.key_pressed(egui::Key::Escape));
+
+        egui::Window::new("Rename Book")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                let edit = ui.add(egui::TextEdit::singleline(name).desired_width(400.0));
+                if edit.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                    confirm = true;
+                } else if !edit.has_focus() && !edit.lost_focus() {
+                    edit.request_focus();
+                }
+                ui.horizontal(|ui| {
+                    confirm |= ui.button("Rename").clicked();
+                    cancel |= ui.button("Cancel").clicked();
+                });
+            });
+
+        if cancel {
+            self.rename_input = None;
+        } else if confirm {
+            if let Some(name) = self.rename_input.take() {
+                self.rename_book(&name, ctx);
+            }
+        }
+    }
+
+    fn show_trash_confirm(&mut self, ctx: &egui::Context) {
+        if !self.trash_confirm {
+            return;
+        }
+        let Some(name) = self.zip_path.as_ref().map(|path| path.file_name().unwrap_or_default().to_string_lossy().to_string()) else {
+            self.trash_confirm = false;
+            return;
+        };
+        let mut confirm = ctx.input(|i| i.key_pressed(egui::Key::Enter));
+        let mut cancel = ctx.input(|i| i.key_pressed(egui::Key::Escape));
+
+        egui::Window::new("Trash Book")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(format!("Move {} to the trash?", name));
+                ui.horizontal(|ui| {
+                    confirm |= ui.button("Trash").clicked();
+                    cancel |= ui.button("Cancel").clicked();
+                });
+            });
+
+        if cancel {
+            self.trash_confirm = false;
+        } else if confirm {
+            self.trash_confirm = false;
+            self.trash_book(ctx);
+        }
+    }
+
+    /// What the last curation action did, with a button to take it back
+    fn show_curation_notice(&mut self, ctx: &egui::Context) {
+        let (Some(shown_at), Some(curation)) = (self.curation_notice, &self.last_curation) else { return };
+        if shown_at.elapsed() > CURATION_NOTICE_TIME {
+            self.curation_notice = None;
+            return;
+        }
+        ctx.request_repaint_after(CURATION_NOTICE_TIME.saturating_sub(shown_at.elapsed()));
+        let text = curation.describe();
+
+        egui::Area::new(egui::Id::new("curation_notice"))
+            .anchor(egui::Align2::CENTER_BOTTOM, [0.0, -110.0])
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label(text);
+                        if ui.button("Undo").clicked() {
+                            self.undo_curation(ctx);
+                        }
+                        if ui.small_button("❌").clicked() {
+                            self.curation_notice = None;
+                        }
+                    });
+                });
+            });
+    }
+
+    fn set_library(&mut self, enabled: bool, ctx: &egui::Context) {
+        self.show_library = enabled;
+        if enabled && !self.library.is_scanned() {
+            self.library.scan(&self.config.library_roots, &self.config.file_sorting, self.config.enable_auto_image_byte_fix, ctx);
+        }
+    }
+
+    /// Ask for a folder or file on a separate thread, the answer comes back through `pick_rx`
+    fn pick_path(&mut self, purpose: PickPurpose) {
+        if !self.is_dialog_open {
+            self.is_dialog_open = true;
+            let sender = self.pick_tx.clone();
+            let book_name = self.zip_path.as_ref().and_then(|p| p.file_stem()).map(|n| n.to_string_lossy().to_string());
+            std::thread::spawn(move || {
+                let dialog = rfd::FileDialog::new();
+                let path = match purpose {
+                    PickPurpose::LibraryRoot | PickPurpose::NavigationRoot | PickPurpose::CurationTarget => dialog.pick_folder(),
+                    PickPurpose::ExportBookmarks => dialog
+                        .add_filter("JSON", &["json"])
+                        .set_file_name(format!("{} bookmarks.json", book_name.unwrap_or_default()))
+                        .save_file(),
+                    PickPurpose::ImportBookmarks => dialog.add_filter("JSON", &["json"]).pick_file(),
+                };
+                let _ = sender.send((purpose, path));
+            });
+        }
+    }
+
+    fn add_library_root(&mut self) {
+        self.pick_path(PickPurpose::LibraryRoot);
+    }
+
+    /// Cover grid of the books under the library folders, with a filter and sort bar above it
+    fn show_library_view(&mut self, ui: &mut egui::Ui, rect: Rect, ctx: &egui::Context) {
+        if ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+            self.show_library = false;
+            return;
+        }
+
+        let mut child = ui.new_child(egui::UiBuilder::new().max_rect(rect.shrink(10.0)));
+        let ui = &mut child;
+        ui.horizontal(|ui| {
+            ui.heading("Library");
+            ui.add_space(20.0);
+            ui.add(egui::TextEdit::singleline(&mut self.library_filter).hint_text("Filter").desired_width(200.0));
+            egui::ComboBox::from_id_salt("library_sort")
+                .selected_text(match self.config.library_sort {
+                    LibrarySort::Title => "Title",
+                    LibrarySort::Modified => "Date Modified",
+                    LibrarySort::PageCount => "Page Count",
+                })
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.config.library_sort, LibrarySort::Title, "Title");
+                    ui.selectable_value(&mut self.config.library_sort, LibrarySort::Modified, "Date Modified");
+                    ui.selectable_value(&mut self.config.library_sort, LibrarySort::PageCount, "Page Count");
+                });
+            let direction = if self.config.library_sort_descending { "⬇" } else { "⬆" };
+            if ui.button(direction).on_hover_text("Sort Direction").clicked() {
+                self.config.library_sort_descending = !self.config.library_sort_descending;
+            }
+            if ui.button("Rescan").clicked() {
+                self.library.scan(&self.config.library_roots, &self.config.file_sorting, self.config.enable_auto_image_byte_fix, ctx);
+            }
+            if ui.button("Add Folder").clicked() {
+                self.add_library_root();
+            }
+            let status = if self.library.scanning { format!("Scanning... {} books", self.library.books.len()) } else { format!("{} books", self.library.books.len()) };
+            ui.label(status);
+        });
+        ui.separator();
+
+        if self.config.library_roots.is_empty() {
+            ui.centered_and_justified(|ui| {
+                ui.label(egui::RichText::new("Add the folders of your collection with \"Add Folder\"").size(20.0));
+            });
+            return;
+        }
+
+        let view = self.library.sorted_view(self.config.library_sort, self.config.library_sort_descending, &self.library_filter);
+        let spacing = ui.spacing().item_spacing;
+        let cell_size = LIBRARY_COVER_SIZE + egui::vec2(0.0, 40.0);
+        let usable_width = ui.available_width() - ui.spacing().scroll.bar_width;
+        let columns = (((usable_width + spacing.x) / (cell_size.x + spacing.x)).floor() as usize).max(1);
+        let rows = view.len().div_ceil(columns);
+
+        let mut open = None;
+        egui::ScrollArea::vertical().auto_shrink(false).show_rows(ui, cell_size.y, rows, |ui, row_range| {
+            for row in row_range {
+                ui.horizontal(|ui| {
+                    for &index in view.iter().skip(row * columns).take(columns) {
+                        let (cell, response) = ui.allocate_exact_size(cell_size, egui::Sense::click());
+                        if response.clicked() {
+                            open = Some(index);
+                        }
+                        let cover = self.library.cover(index).cloned();
+                        let book = &self.library.books[index];
+                        let visuals = ui.visuals();
+                        let painter = ui.painter();
+                        if response.hovered() {
+                            painter.rect_filled(cell, 6.0, visuals.widgets.hovered.weak_bg_fill);
+                        }
+
+                        let cover_slot = Rect::from_min_size(cell.min, LIBRARY_COVER_SIZE).shrink(4.0);
+                        match cover {
+                            Some(tex) => {
+                                let size = tex.size_vec2() * (cover_slot.width() / tex.size_vec2().x).min(cover_slot.height() / tex.size_vec2().y);
+                                let cover_rect = Rect::from_center_size(egui::pos2(cover_slot.center().x, cover_slot.bottom() - size.y / 2.0), size);
+                                painter.image(tex.id(), cover_rect, Rect::from_min_max(egui::Pos2::ZERO, egui::pos2(1.0, 1.0)), egui::Color32::WHITE);
+                            }
+                            None => {
+                                painter.rect_filled(cover_slot, 0.0, egui::Color32::from_gray(60));
+                            }
+                        }
+
+                        let title = painter.layout(
+                            book.title.clone(),
+                            egui::FontId::proportional(14.0),
+                            visuals.strong_text_color(),
+                            cell.width() - 8.0,
+                        );
+                        let title_pos = egui::pos2(cell.left() + 4.0, cell.top() + LIBRARY_COVER_SIZE.y + 2.0);
+                        let title_height = title.rows.first().map_or(0.0, |row| row.rect().height());
+                        // One line of the title, the rest is in the hover text
+                        painter.with_clip_rect(Rect::from_min_size(title_pos, egui::vec2(cell.width() - 8.0, title_height)))
+                            .galley(title_pos, title, visuals.text_color());
+                        // A status set by hand wins over the history
+                        let record = self.book_db.get(&book.id);
+                        let status = record.map_or(ReadStatus::Unread, |record| record.read_status());
+                        let progress = match status {
+                            ReadStatus::Unread => None,
+                            ReadStatus::InProgress => Some(record.and_then(|record| record.history.as_ref()).map_or(0.0, |history| history.progress().min(1.0))),
+                            ReadStatus::Finished => Some(1.0),
+                        };
+                        let details = match progress {
+                            Some(progress) if status == ReadStatus::InProgress => format!("{} pages · {:.0}%", book.page_count, progress * 100.0),
+                            _ => format!("{} pages · {}", book.page_count, status.label()),
+                        };
+                        painter.text(
+                            egui::pos2(cell.left() + 4.0, title_pos.y + title_height + 2.0),
+                            egui::Align2::LEFT_TOP,
+                            details,
+                            egui::FontId::proportional(12.0),
+                            visuals.weak_text_color(),
+                        );
+                        if let Some(progress) = progress {
+                            // Read progress along the bottom of the cover
+                            let bar = Rect::from_min_size(egui::pos2(cover_slot.left(), cover_slot.bottom() - 4.0), egui::vec2(cover_slot.width(), 4.0));
+                            painter.rect_filled(bar, 0.0, egui::Color32::from_black_alpha(160));
+                            let filled = Rect::from_min_size(bar.min, egui::vec2(bar.width() * progress, bar.height()));
+                            painter.rect_filled(filled, 0.0, visuals.selection.bg_fill);
+                        }
+                        response.on_hover_text(book.path.to_string_lossy());
+                    }
+                });
+            }
+        });
+
+        if let Some(index) = open {
+            let path = self.library.books[index].path.clone();
+            self.show_library = false;
+            self.load_source(path, ctx);
+        }
+    }
+
+    /// Decode the visible pages again at their original size (up to the GPU limit),
+    /// with the same crop and filters as the screen textures. The worker makes them,
+    /// the screen textures are magnified until they are done.
+    fn update_loupe_textures(&mut self, ctx: &egui::Context) {
+        let key = self.textures.each_ref().map(|tex| tex.as_ref().map(|tex| tex.name()));
+        if self.loupe_key.as_ref() == Some(&key) {
+            return;
+        }
+
+        match self.pending_loupe.take() {
+            Some((pending_key, id)) if pending_key == key => match self.loupe_loader.poll(id) {
+                Some(prepared) => {
+                    self.loupe_textures = prepared.pages.map(|page| page.map(|page| {
+                        ctx.load_texture(format!("{}#loupe", page.cache_name), page.image, egui::TextureOptions::LINEAR)
+                    }));
+                    self.loupe_key = Some(key);
+                }
+                None => self.pending_loupe = Some((pending_key, id)),
+            },
+            _ => {
+                let specs = self.pair_specs(self.current_index, None);
+                let id = self.loupe_loader.submit(self.page_recipe(ctx), specs, ctx);
+                self.pending_loupe = Some((key, id));
+                self.loupe_textures = [None, None];
+            }
+        }
+    }
+
+    /// Make the loupe textures again, the pages or their filters changed
+    fn forget_loupe(&mut self) {
+        self.loupe_key = None;
+        self.pending_loupe = None;
+    }
+
+    /// Circular magnifier under the cursor while the magnifier key or the middle button is held
+    fn show_loupe(&mut self, ctx: &egui::Context) {
+        let shortcut = self.config.keys.magnifier;
+        let typing = ctx.wants_keyboard_input();
+        let (held, pointer_pos) = ctx.input(|i| {
+            let key_held = !typing && i.key_down(shortcut.key) && i.modifiers.ctrl == shortcut.ctrl &&
+                i.modifiers.alt == shortcut.alt && i.modifiers.shift == shortcut.shift;
+            (key_held || i.pointer.middle_down(), i.pointer.hover_pos())
+        });
+        if !held || self.binding_action.is_some() || self.panel_edit {
+            return;
+        }
+        let Some(pos) = pointer_pos.filter(|pos| self.view_rect.contains(*pos)) else { return };
+        let Some((slot, page_rect)) = self.page_rects.iter()
+            .enumerate()
+            .find_map(|(slot, r)| r.filter(|r| r.contains(pos)).map(|r| (slot, r))) else { return };
+
+        self.update_loupe_textures(ctx);
+        let Some(tex) = self.loupe_textures[slot].as_ref().or(self.textures[slot].as_ref()) else { return };
+
+        // The magnification is relative to the page as it is shown
+        let radius = self.config.loupe_size / 2.0;
+        let center_uv = ((pos - page_rect.min) / page_rect.size()).to_pos2();
+        let uv_radius = egui::Vec2::splat(radius / self.config.loupe_magnification.max(1.0)) / page_rect.size();
+
+        let color = egui::Color32::WHITE;
+        let mut mesh = egui::Mesh::with_texture(tex.id());
+        mesh.vertices.push(egui::epaint::Vertex { pos, uv: center_uv, color });
+        for i in 0..=LOUPE_SEGMENTS {
+            let angle = std::f32::consts::TAU * i as f32 / LOUPE_SEGMENTS as f32;
+            let dir = egui::vec2(angle.cos(), angle.sin());
+            mesh.vertices.push(egui::epaint::Vertex { pos: pos + dir * radius, uv: center_uv + dir * uv_radius, color });
+        }
+        for i in 1..=LOUPE_SEGMENTS {
+            mesh.add_triangle(0, i, i + 1);
         }
 
-        self.reset_buffer();
-        self.texture_cache.clear();
-        self.textures = self.load_pair(self.current_index, ctx);
-        let msg = if self.is_shifted { "Mode: Odd Page" } else { "Mode: Even Page" };
-        self.show_fading_error(msg);
+        let painter = ctx.layer_painter(egui::LayerId::new(egui::Order::Tooltip, egui::Id::new("loupe")));
+        painter.add(egui::Shape::mesh(mesh));
+        painter.circle_stroke(pos, radius, egui::Stroke::new(2.0, egui::Color32::from_gray(200)));
+        ctx.set_cursor_icon(egui::CursorIcon::None);
     }
 }
 
@@ -819,6 +3031,30 @@ impl eframe::App for MangaReader {
                             "View Mode" => self.config.keys.view_mode = new_shortcut,
                             "Open File" => self.config.keys.open_file = new_shortcut,
                             "Quit App" => self.config.keys.quit_app = new_shortcut,
+                            "Toggle Blank Page" => self.config.keys.toggle_blank_page = new_shortcut,
+                            "Toggle Auto Crop" => self.config.keys.toggle_auto_crop = new_shortcut,
+                            "Toggle Night Mode" => self.config.keys.toggle_night_mode = new_shortcut,
+                            "Pan Up" => self.config.keys.pan_up = new_shortcut,
+                            "Pan Down" => self.config.keys.pan_down = new_shortcut,
+                            "Pan Left" => self.config.keys.pan_left = new_shortcut,
+                            "Pan Right" => self.config.keys.pan_right = new_shortcut,
+                            "Zoom In" => self.config.keys.zoom_in = new_shortcut,
+                            "Zoom Out" => self.config.keys.zoom_out = new_shortcut,
+                            "Reset Zoom" => self.config.keys.reset_zoom = new_shortcut,
+                            "Toggle Panel View" => self.config.keys.toggle_panel_view = new_shortcut,
+                            "Page Overview" => self.config.keys.toggle_page_overview = new_shortcut,
+                            "Library" => self.config.keys.toggle_library = new_shortcut,
+                            "Reopen Last" => self.config.keys.reopen_last = new_shortcut,
+                            "Toggle Bookmark" => self.config.keys.toggle_bookmark = new_shortcut,
+                            "Next Bookmark" => self.config.keys.next_bookmark = new_shortcut,
+                            "Prev Bookmark" => self.config.keys.prev_bookmark = new_shortcut,
+                            "Next Unread" => self.config.keys.next_unread = new_shortcut,
+                            "Move Book" => self.config.keys.move_book = new_shortcut,
+                            "Copy Book" => self.config.keys.copy_book = new_shortcut,
+                            "Trash Book" => self.config.keys.trash_book = new_shortcut,
+                            "Rename Book" => self.config.keys.rename_book = new_shortcut,
+                            "Undo Curation" => self.config.keys.undo_curation = new_shortcut,
+                            "Magnifier" => self.config.keys.magnifier = new_shortcut,
                             _ => {}
                         }
                         self.binding_action = None;
@@ -850,12 +3086,45 @@ impl eframe::App for MangaReader {
                 if is_triggered(&keys.view_mode) { action_to_run = MangaAction::ViewMode; }
                 if is_triggered(&keys.open_file) { action_to_run = MangaAction::OpenFile; }
                 if is_triggered(&keys.quit_app) { action_to_run = MangaAction::QuitApp; }
+                if is_triggered(&keys.toggle_blank_page) { action_to_run = MangaAction::ToggleBlankPage; }
+                if is_triggered(&keys.toggle_auto_crop) { action_to_run = MangaAction::ToggleAutoCrop; }
+                if is_triggered(&keys.toggle_night_mode) { action_to_run = MangaAction::ToggleNightMode; }
+                if is_triggered(&keys.pan_up) { action_to_run = MangaAction::PanUp; }
+                if is_triggered(&keys.pan_down) { action_to_run = MangaAction::PanDown; }
+                if is_triggered(&keys.pan_left) { action_to_run = MangaAction::PanLeft; }
+                if is_triggered(&keys.pan_right) { action_to_run = MangaAction::PanRight; }
+                if is_triggered(&keys.zoom_in) { action_to_run = MangaAction::ZoomIn; }
+                if is_triggered(&keys.zoom_out) { action_to_run = MangaAction::ZoomOut; }
+                if is_triggered(&keys.reset_zoom) { action_to_run = MangaAction::ResetZoom; }
+                if is_triggered(&keys.toggle_panel_view) { action_to_run = MangaAction::TogglePanelView; }
+                if is_triggered(&keys.toggle_page_overview) { action_to_run = MangaAction::TogglePageOverview; }
+                if is_triggered(&keys.toggle_library) { action_to_run = MangaAction::ToggleLibrary; }
+                if is_triggered(&keys.reopen_last) { action_to_run = MangaAction::ReopenLast; }
+                if is_triggered(&keys.toggle_bookmark) { action_to_run = MangaAction::ToggleBookmark; }
+                if is_triggered(&keys.next_bookmark) { action_to_run = MangaAction::NextBookmark; }
+                if is_triggered(&keys.prev_bookmark) { action_to_run = MangaAction::PrevBookmark; }
+                if is_triggered(&keys.next_unread) { action_to_run = MangaAction::NextUnread; }
+                if is_triggered(&keys.move_book) { action_to_run = MangaAction::MoveBook; }
+                if is_triggered(&keys.copy_book) { action_to_run = MangaAction::CopyBook; }
+                if is_triggered(&keys.trash_book) { action_to_run = MangaAction::TrashBook; }
+                if is_triggered(&keys.rename_book) { action_to_run = MangaAction::RenameBook; }
+                if is_triggered(&keys.undo_curation) { action_to_run = MangaAction::UndoCuration; }
             });
         }
 
+        // The library and the overview grid handle the arrows, Enter and Escape themselves,
+        // the target list and text fields like the rename box or the library filter take every key
+        if ctx.wants_keyboard_input() || self.target_picker.is_some() || self.rename_input.is_some() || self.trash_confirm {
+            action_to_run = MangaAction::None;
+        } else if self.show_library && !matches!(action_to_run, MangaAction::ToggleLibrary | MangaAction::FullScreen) {
+            action_to_run = MangaAction::None;
+        } else if self.page_overview && !matches!(action_to_run, MangaAction::TogglePageOverview | MangaAction::FullScreen) {
+            action_to_run = MangaAction::None;
+        }
+
         match action_to_run {
-            MangaAction::NextPage => self.next_page(ctx),
-            MangaAction::PrevPage => self.prev_page(ctx),
+            MangaAction::NextPage => self.guided_step(true, ctx),
+            MangaAction::PrevPage => self.guided_step(false, ctx),
             MangaAction::FirstPage => self.go_to_first_page(ctx),
             MangaAction::LastPage => self.go_to_last_page(ctx),
             MangaAction::NextFile => self.next_zip(ctx),
@@ -874,14 +3143,60 @@ impl eframe::App for MangaReader {
                 ctx.send_viewport_cmd(egui::ViewportCommand::Close);
             },
             MangaAction::OpenFile => self.open_file_dialog(),
+            MangaAction::ToggleBlankPage => self.toggle_blank_page(ctx),
+            MangaAction::ToggleAutoCrop => self.toggle_auto_crop(ctx),
+            MangaAction::ToggleNightMode => self.set_night_mode(!self.config.night_mode, ctx),
+            MangaAction::PanUp => self.pan(egui::vec2(0.0, -1.0)),
+            MangaAction::PanDown => self.pan(egui::vec2(0.0, 1.0)),
+            MangaAction::PanLeft => self.pan(egui::vec2(-1.0, 0.0)),
+            MangaAction::PanRight => self.pan(egui::vec2(1.0, 0.0)),
+            MangaAction::ZoomIn => self.zoom_at(self.zoom_factor * ZOOM_STEP, None),
+            MangaAction::ZoomOut => self.zoom_at(self.zoom_factor / ZOOM_STEP, None),
+            MangaAction::ResetZoom => self.zoom_at(1.0, None),
+            MangaAction::TogglePanelView => self.set_panel_view(!self.panel_view, ctx),
+            MangaAction::TogglePageOverview => self.set_page_overview(!self.page_overview),
+            MangaAction::ToggleLibrary => self.set_library(!self.show_library, ctx),
+            MangaAction::ReopenLast => self.reopen_last(ctx),
+            MangaAction::ToggleBookmark => self.toggle_bookmark(),
+            MangaAction::NextBookmark => self.bookmark_step(true, ctx),
+            MangaAction::PrevBookmark => self.bookmark_step(false, ctx),
+            MangaAction::NextUnread => self.next_unread(ctx),
+            MangaAction::MoveBook => self.open_target_picker(TargetPicker::Move),
+            MangaAction::CopyBook => self.open_target_picker(TargetPicker::Copy),
+            MangaAction::TrashBook => {
+                // A key without modifiers, like the default Delete, is easy to hit by accident
+                let keys = &self.config.keys.trash_book;
+                if keys.ctrl || keys.alt || keys.shift {
+                    self.trash_book(ctx);
+                } else {
+                    self.trash_confirm = self.zip_path.is_some();
+                }
+            }
+            MangaAction::RenameBook => {
+                self.rename_input = self.zip_path.as_ref()
+                    .map(|path| path.file_name().unwrap_or_default().to_string_lossy().to_string());
+            }
+            MangaAction::UndoCuration => self.undo_curation(ctx),
             MangaAction::None => {},
         }
 
-        // Load file if passed as program parameter
+        // Load file if passed as program parameter, or the book of the last session
+        if self.initial_path.is_none() && self.config.restore_session {
+            self.initial_path = self.pending_session.as_ref().and_then(|s| s.book.clone());
+        }
         if let Some(p) = self.initial_path.as_ref() {
             self.load_source(p.clone(), ctx);
             self.initial_path = None;
         }
+        if let Some(session) = self.pending_session.take() {
+            self.restore_session(session, ctx);
+        }
+
+        self.check_night_schedule(ctx);
+        // Night mode saved from the last session
+        if self.config.night_mode && self.day_visuals.is_none() {
+            self.update_night_visuals(ctx);
+        }
 
         // File Dialog Result
         if let Ok(result) = self.dialog_rx.try_recv() {
@@ -890,18 +3205,61 @@ impl eframe::App for MangaReader {
                 self.load_source(path, ctx);
             }
         }
+        if let Some(changes) = self.folder_watcher.as_mut().and_then(|watcher| watcher.poll(ctx)) {
+            if changes.pages && self.source_mode == SourceMode::Folder {
+                self.refresh_folder_pages(&changes.changed_pages, ctx);
+            }
+            if changes.siblings {
+                // New books may be anywhere in the walk
+                self.source_walk = None;
+                if let Some(parent) = self.zip_path.as_ref().and_then(|p| p.parent()).map(Path::to_path_buf) {
+                    self.all_zips_in_folder = self.scan_folder(&parent, ctx);
+                }
+            }
+        }
+        if let Ok((purpose, result)) = self.pick_rx.try_recv() {
+            self.is_dialog_open = false;
+            if let Some(path) = result {
+                match purpose {
+                    PickPurpose::LibraryRoot => {
+                        if !self.config.library_roots.contains(&path) {
+                            self.config.library_roots.push(path);
+                            self.library.scan(&self.config.library_roots, &self.config.file_sorting, self.config.enable_auto_image_byte_fix, ctx);
+                        }
+                    }
+                    PickPurpose::NavigationRoot => self.config.navigation_root = Some(path),
+                    PickPurpose::CurationTarget => {
+                        if !self.config.curation_targets.contains(&path) {
+                            self.config.curation_targets.push(path);
+                        }
+                    }
+                    PickPurpose::ExportBookmarks => self.export_bookmarks(&path),
+                    PickPurpose::ImportBookmarks => self.import_bookmarks(&path),
+                }
+            }
+        }
+
+        // Ctrl + wheel and pinch zoom around the cursor
+        let (zoom_delta, hover_pos) = ctx.input(|i| (i.zoom_delta(), i.pointer.hover_pos()));
+        if self.zip_path.is_some() && !self.panel_view && (zoom_delta - 1.0).abs() > 0.001 {
+            self.zoom_at(self.zoom_factor * zoom_delta, hover_pos);
+        }
+        self.update_zoom_textures(ctx);
 
         // INSTANT STATE-BASED SCROLLING
         let scroll_delta = ctx.input(|i| i.smooth_scroll_delta);
         let scroll_threshold = 2.0;
 
-        // If the wheel is moving significantly
-        if scroll_delta.y.abs() > scroll_threshold || scroll_delta.x.abs() > scroll_threshold {
+        // When zoomed the wheel scrolls through the page instead of turning it,
+        // in the overview it scrolls the grid
+        if self.is_zoomed() || self.page_overview || self.show_library {
+            self.can_scroll = true;
+        } else if scroll_delta.y.abs() > scroll_threshold || scroll_delta.x.abs() > scroll_threshold {
             if self.can_scroll {
                 if scroll_delta.y < -scroll_threshold || scroll_delta.x < -scroll_threshold {
-                    self.next_page(ctx);
+                    self.guided_step(true, ctx);
                 } else if scroll_delta.y > scroll_threshold || scroll_delta.x > scroll_threshold {
-                    self.prev_page(ctx);
+                    self.guided_step(false, ctx);
                 }
                 // Lock the scrolling until it stops
                 self.can_scroll = false;
@@ -911,6 +3269,12 @@ impl eframe::App for MangaReader {
             self.can_scroll = true;
         }
 
+        self.finish_progressive_load(ctx);
+        self.thumbnails.poll(ctx);
+        self.library.poll(ctx);
+        self.poll_folder_sort();
+        self.poll_spread_scan(ctx);
+
         if self.config.show_settings {
             egui::SidePanel::right("settings_panel")
                 .resizable(true) // Enable mouse dragging
@@ -973,6 +3337,9 @@ impl eframe::App for MangaReader {
                                     changed |= ui.radio_value(&mut self.config.resize_method, ResizeMethod::Triangle, egui::RichText::new("Bilinear (Balance)")).clicked();
                                     changed |= ui.radio_value(&mut self.config.resize_method, ResizeMethod::CatmullRom, egui::RichText::new("Bicubic")).clicked();
                                     changed |= ui.radio_value(&mut self.config.resize_method, ResizeMethod::Lanczos3, egui::RichText::new("Lanczos3 (High Quality, Slow)")).clicked();
+                                    changed |= ui.checkbox(&mut self.config.resize_dither, "Dither")
+                                        .on_hover_text("Ordered dither after scaling, hides banding in smooth gradients.")
+                                        .changed();
 
                                     if changed {
                                         self.reset_buffer();
@@ -993,10 +3360,64 @@ impl eframe::App for MangaReader {
                                     changed |= ui.radio_value(&mut self.config.page_view_options, PageViewOptions::DoubleLR, egui::RichText::new("Double Page(Left to Right)")).clicked();
 
                                     if changed {
-                                        self.reset_buffer();
-                                        self.textures = self.load_pair(self.current_index, ctx);
+                                        // Split pages follow the reading direction
+                                        self.rebuild_pages(ctx);
                                         self.save_settings();
                                     }
+
+                                    if self.zip_path.is_some() {
+                                        let mut book_view = self.view_mode;
+                                        let view_text = |value: Option<PageViewOptions>| match value {
+                                            None => "Use default",
+                                            Some(PageViewOptions::Single) => "Single Page",
+                                            Some(PageViewOptions::DoubleRL) => "Double Page(Right to Left)",
+                                            Some(PageViewOptions::DoubleLR) => "Double Page(Left to Right)",
+                                        };
+                                        egui::ComboBox::new("book_view_mode", "This book")
+                                            .selected_text(view_text(book_view))
+                                            .show_ui(ui, |ui| {
+                                                for value in [None, Some(PageViewOptions::Single), Some(PageViewOptions::DoubleRL), Some(PageViewOptions::DoubleLR)] {
+                                                    ui.selectable_value(&mut book_view, value, view_text(value));
+                                                }
+                                            });
+                                        if book_view != self.view_mode {
+                                            // Kept in the reading history of the book
+                                            self.view_mode = book_view;
+                                            self.rebuild_pages(ctx);
+                                        }
+                                    }
+                                }
+
+                                ui.add_space(5.0);
+                                {
+                                    let changed = ui.checkbox(&mut self.config.split_spreads, "Split double page scans")
+                                        .on_hover_text("Landscape images are shown as two pages, in the reading direction.")
+                                        .changed();
+                                    if changed {
+                                        self.rebuild_pages(ctx);
+                                        self.save_settings();
+                                    }
+
+                                    if let Some(source_path) = self.zip_path.clone() {
+                                        let mut book_split = self.book_settings.split_spreads;
+                                        let split_text = |value: Option<bool>| match value {
+                                            None => "Use default",
+                                            Some(true) => "Split",
+                                            Some(false) => "Don't split",
+                                        };
+                                        egui::ComboBox::from_label("This book")
+                                            .selected_text(split_text(book_split))
+                                            .show_ui(ui, |ui| {
+                                                for value in [None, Some(true), Some(false)] {
+                                                    ui.selectable_value(&mut book_split, value, split_text(value));
+                                                }
+                                            });
+                                        if book_split != self.book_settings.split_spreads {
+                                            self.book_settings.split_spreads = book_split;
+                                            self.book_settings_store.set(&source_path, self.book_settings.clone());
+                                            self.rebuild_pages(ctx);
+                                        }
+                                    }
                                 }
 
                                 ui.add_space(20.0);
@@ -1016,26 +3437,274 @@ impl eframe::App for MangaReader {
                                     }
                                 }
 
+                                if self.book_id.is_some() {
+                                    ui.add_space(20.0);
+                                    ui.label(egui::RichText::new("This Book:").size(20.0).strong());
+                                    separator_pct(ui);
+                                    self.book_info_ui(ui);
+                                }
+
                                 ui.add_space(20.0);
-                                ui.label(egui::RichText::new("Zoom:").size(20.0).strong());
+                                ui.label(egui::RichText::new("File Order:").size(20.0).strong());
                                 separator_pct(ui);
+                                self.file_order_ui(ui, ctx);
 
-                                let zoom_slider = ui.add(egui::Slider::new(&mut self.zoom_factor, 0.5..=3.0).text("Zoom x"));
-                                let is_scrubbing_zoom = zoom_slider.dragged();
-                                if zoom_slider.changed() && !is_scrubbing_zoom {
-                                    // If we zoom, and we aren't in single page mode, force it (as per your requirement)
-                                    if self.zoom_factor != 1.0 {
-                                        self.reset_buffer();
-                                        self.texture_cache.clear();
-                                        self.textures = self.load_pair(self.current_index, ctx);
-                                        self.config.page_view_options = PageViewOptions::Single;
+                                ui.add_space(20.0);
+                                ui.label(egui::RichText::new("Reopening a Book:").size(20.0).strong());
+                                separator_pct(ui);
+                                if ui.checkbox(&mut self.config.skip_finished_files, "Skip finished files")
+                                    .on_hover_text("Next/previous file passes over the files marked as finished.")
+                                    .changed()
+                                {
+                                    self.save_settings();
+                                }
+                                if ui.add(egui::Slider::new(&mut self.config.recent_files_max, 1..=50).text("Recent files")).changed() {
+                                    self.config.recent_files.truncate(self.config.recent_files_max);
+                                }
+                                if ui.checkbox(&mut self.config.restore_session, "Restore last session on startup")
+                                    .on_hover_text("Reopen the last book, page, view mode, zoom, fullscreen state and window position.")
+                                    .changed()
+                                {
+                                    if self.config.restore_session {
+                                        self.save_session();
+                                    } else {
+                                        Session::clear();
+                                    }
+                                    self.save_settings();
+                                }
+
+                                {
+                                    let mut changed = false;
+                                    changed |= ui.radio_value(&mut self.config.resume_mode, ResumeMode::Resume, egui::RichText::new("Continue at the Last Page")).clicked();
+                                    changed |= ui.radio_value(&mut self.config.resume_mode, ResumeMode::Ask, egui::RichText::new("Ask")).clicked();
+                                    changed |= ui.radio_value(&mut self.config.resume_mode, ResumeMode::StartOver, egui::RichText::new("Start from the First Page")).clicked();
+
+                                    if changed {
+                                        self.save_settings();
+                                    }
+                                }
+
+                                ui.add_space(20.0);
+                                ui.label(egui::RichText::new("Crop Margins:").size(20.0).strong());
+                                separator_pct(ui);
+
+                                {
+                                    let mut changed = ui.checkbox(&mut self.config.auto_crop, "Auto crop page margins")
+                                        .on_hover_text("Cut the uniform white or black borders around each page.")
+                                        .changed();
+                                    let tolerance_slider = ui.add(egui::Slider::new(&mut self.config.auto_crop_tolerance, 0..=100)
+                                        .text("Noise tolerance"))
+                                        .on_hover_text("How much a border pixel may differ from the border colour.");
+                                    changed |= tolerance_slider.drag_stopped() || (tolerance_slider.changed() && !tolerance_slider.dragged());
+
+                                    if let Some(source_path) = self.zip_path.clone() {
+                                        let mut manual = self.book_settings.crop_override.is_some();
+                                        let mut book_changed = ui.checkbox(&mut manual, "Manual crop for this book").changed();
+                                        let mut crop = self.book_settings.crop_override.unwrap_or_default();
+                                        if manual {
+                                            for (value, label) in [(&mut crop.left, "Left %"), (&mut crop.top, "Top %"), (&mut crop.right, "Right %"), (&mut crop.bottom, "Bottom %")] {
+                                                let mut percent = *value * 100.0;
+                                                let slider = ui.add(egui::Slider::new(&mut percent, 0.0..=40.0).text(label));
+                                                *value = percent / 100.0;
+                                                book_changed |= slider.drag_stopped() || (slider.changed() && !slider.dragged());
+                                            }
+                                        }
+                                        if book_changed {
+                                            self.book_settings.crop_override = if manual { Some(crop) } else { None };
+                                            self.book_settings_store.set(&source_path, self.book_settings.clone());
+                                            changed = true;
+                                        } else if manual {
+                                            // Keep the slider values while dragging, they are applied on release
+                                            self.book_settings.crop_override = Some(crop);
+                                        }
+                                    }
+
+                                    if changed {
+                                        self.reload_textures(ctx);
+                                        self.save_settings();
+                                    }
+                                }
+
+                                ui.add_space(20.0);
+                                ui.label(egui::RichText::new("Image Adjustments:").size(20.0).strong());
+                                separator_pct(ui);
+
+                                {
+                                    let mut book_only = self.book_settings.adjustments.is_some();
+                                    let mut adjustments = self.adjustments();
+                                    let mut changed = false; // apply right away, with a full reload
+                                    let mut previewing = false; // slider still dragged, only refresh the screen
+
+                                    if self.zip_path.is_some() {
+                                        changed |= ui.checkbox(&mut book_only, "Only for this book")
+                                            .on_hover_text("Save these adjustments for the current book instead of all books.")
+                                            .changed();
+                                    }
+                                    changed |= ui.checkbox(&mut adjustments.auto_levels, "Auto levels").changed();
+                                    changed |= ui.checkbox(&mut adjustments.remove_tint, "Remove colour tint")
+                                        .on_hover_text("Neutralize the yellowed paper of old scans.")
+                                        .changed();
+                                    changed |= ui.checkbox(&mut adjustments.grayscale, "Grayscale").changed();
+
+                                    let sliders = [
+                                        ui.add(egui::Slider::new(&mut adjustments.brightness, -1.0..=1.0).text("Brightness")),
+                                        ui.add(egui::Slider::new(&mut adjustments.contrast, -1.0..=1.0).text("Contrast")),
+                                        ui.add(egui::Slider::new(&mut adjustments.gamma, 0.2..=3.0).text("Gamma")),
+                                        ui.add(egui::Slider::new(&mut adjustments.sharpen, 0.0..=3.0).text("Sharpen")),
+                                    ];
+                                    for slider in sliders {
+                                        changed |= slider.drag_stopped() || (slider.changed() && !slider.dragged());
+                                        previewing |= slider.changed() && slider.dragged();
+                                    }
+                                    if ui.button("Reset Adjustments").clicked() {
+                                        adjustments = ImageAdjustments::default();
+                                        changed = true;
+                                    }
+
+                                    if book_only {
+                                        self.book_settings.adjustments = Some(adjustments);
+                                    } else if self.book_settings.adjustments.take().is_none() {
+                                        // Leaving "only for this book" goes back to the global values untouched
+                                        self.config.adjustments = adjustments;
+                                    }
+
+                                    if changed {
+                                        if let Some(source_path) = self.zip_path.clone() {
+                                            self.book_settings_store.set(&source_path, self.book_settings.clone());
+                                        }
+                                        self.save_settings();
+                                        self.reload_textures(ctx);
+                                    } else if previewing {
+                                        self.preview_adjustments(ctx);
+                                    }
+                                }
+
+                                ui.add_space(20.0);
+                                ui.label(egui::RichText::new("Night Mode:").size(20.0).strong());
+                                separator_pct(ui);
+
+                                {
+                                    let mut night_mode = self.config.night_mode;
+                                    if ui.checkbox(&mut night_mode, "Night mode")
+                                        .on_hover_text("Invert black and white pages and dim the interface.")
+                                        .changed() {
+                                        self.set_night_mode(night_mode, ctx);
+                                    }
+
+                                    let mut changed = false;
+                                    ui.label("Colour pages:");
+                                    changed |= ui.radio_value(&mut self.config.night_color_pages, NightColorPages::Keep, "Keep as is").clicked();
+                                    changed |= ui.radio_value(&mut self.config.night_color_pages, NightColorPages::WarmTint, "Warm tint").clicked();
+                                    if self.config.night_color_pages == NightColorPages::WarmTint {
+                                        let warmth_slider = ui.add(egui::Slider::new(&mut self.config.night_warmth, 0.0..=1.0).text("Warmth"));
+                                        changed |= warmth_slider.drag_stopped() || (warmth_slider.changed() && !warmth_slider.dragged());
+                                    }
+
+                                    ui.checkbox(&mut self.config.night_schedule, "Switch on schedule");
+                                    if self.config.night_schedule {
+                                        ui.horizontal(|ui| {
+                                            ui.label("From");
+                                            ui.add(egui::DragValue::new(&mut self.config.night_start_hour).range(0..=23).suffix(":00"));
+                                            ui.label("to");
+                                            ui.add(egui::DragValue::new(&mut self.config.night_end_hour).range(0..=23).suffix(":00"));
+                                        });
+                                    }
+
+                                    if changed {
+                                        self.save_settings();
+                                        if self.config.night_mode {
+                                            self.reload_textures(ctx);
+                                        }
                                     }
                                 }
 
+                                ui.add_space(20.0);
+                                ui.label(egui::RichText::new("Zoom:").size(20.0).strong());
+                                separator_pct(ui);
+
+                                let mut zoom = self.zoom_factor;
+                                if ui.add(egui::Slider::new(&mut zoom, MIN_ZOOM..=MAX_ZOOM).text("Zoom x"))
+                                    .on_hover_text("Ctrl + mouse wheel or pinch zooms around the cursor.")
+                                    .changed() {
+                                    self.zoom_at(zoom, None);
+                                }
+
                                 if ui.button(egui::RichText::new("Reset Zoom")).clicked() {
-                                    self.zoom_factor = 1.0;
+                                    self.zoom_at(1.0, None);
+                                }
+                                ui.checkbox(&mut self.config.guided_pan, "Guided panning when zoomed")
+                                    .on_hover_text("Next/previous page keys walk through the zoomed page in reading order before turning it.");
+                                if self.config.guided_pan {
+                                    let mut overlap = self.config.pan_overlap * 100.0;
+                                    ui.add(egui::Slider::new(&mut overlap, 0.0..=50.0).text("Overlap %"));
+                                    self.config.pan_overlap = overlap / 100.0;
+                                    ui.add(egui::Slider::new(&mut self.config.pan_animation_ms, 0..=1000).text("Animation (ms)"));
+                                }
+                                separator_pct(ui);
+
+                                ui.add_space(20.0);
+                                ui.label(egui::RichText::new("Magnifier:").size(20.0).strong());
+                                separator_pct(ui);
+                                ui.add(egui::Slider::new(&mut self.config.loupe_size, 100.0..=800.0).text("Size (px)"))
+                                    .on_hover_text("Hold the magnifier key or the middle mouse button over a page.");
+                                ui.add(egui::Slider::new(&mut self.config.loupe_magnification, 1.5..=8.0).text("Magnification x"));
+
+                                ui.add_space(20.0);
+                                ui.label(egui::RichText::new("Library:").size(20.0).strong());
+                                separator_pct(ui);
+                                {
+                                    let mut removed = None;
+                                    for (i, root) in self.config.library_roots.iter().enumerate() {
+                                        ui.horizontal(|ui| {
+                                            if ui.small_button("❌").on_hover_text("Remove Folder").clicked() {
+                                                removed = Some(i);
+                                            }
+                                            ui.label(root.to_string_lossy());
+                                        });
+                                    }
+                                    if let Some(i) = removed {
+                                        self.config.library_roots.remove(i);
+                                        self.library.scan(&self.config.library_roots, &self.config.file_sorting, self.config.enable_auto_image_byte_fix, ctx);
+                                    }
+                                    if ui.button("Add Folder").on_hover_text("Folders searched for books, including their subfolders.").clicked() {
+                                        self.add_library_root();
+                                    }
+                                    ui.label("Next/Prev Folder Root:")
+                                        .on_hover_text("Next/Prev folder go through every folder below this one. Without it they use the library folder holding the book, or the folder above the book's folder.");
+                                    ui.horizontal(|ui| {
+                                        if self.config.navigation_root.is_some() && ui.small_button("❌").on_hover_text("Clear").clicked() {
+                                            self.config.navigation_root = None;
+                                        }
+                                        match &self.config.navigation_root {
+                                            Some(root) => ui.label(root.to_string_lossy()),
+                                            None => ui.weak("Not set"),
+                                        };
+                                        if ui.button("Choose").clicked() {
+                                            self.pick_path(PickPurpose::NavigationRoot);
+                                        }
+                                    });
                                 }
+
+                                ui.add_space(20.0);
+                                ui.label(egui::RichText::new("Move/Copy Targets:").size(20.0).strong());
                                 separator_pct(ui);
+                                {
+                                    let mut removed = None;
+                                    for (i, target) in self.config.curation_targets.iter().enumerate() {
+                                        ui.horizontal(|ui| {
+                                            if ui.small_button("❌").on_hover_text("Remove Folder").clicked() {
+                                                removed = Some(i);
+                                            }
+                                            ui.label(format!("{}  {}", i + 1, target.to_string_lossy()));
+                                        });
+                                    }
+                                    if let Some(i) = removed {
+                                        self.config.curation_targets.remove(i);
+                                    }
+                                    if ui.button("Add Folder").on_hover_text("Folders the open book can be moved or copied to, picked by their number.").clicked() {
+                                        self.pick_path(PickPurpose::CurationTarget);
+                                    }
+                                }
 
                                 ui.add_space(20.0);
                                 ui.label(egui::RichText::new("Others:").size(20.0).strong());
@@ -1049,6 +3718,8 @@ impl eframe::App for MangaReader {
                                     .on_hover_text("Cached the image files already load on a single zip file. Cached will be cleared after loading next zip.");
                                 ui.add(egui::Slider::new(&mut self.config.image_delay, 0..=1000)
                                     .text("Image Delay (ms)")).on_hover_text("Delay time in between before the next image shown. Useful when holding next/prev image button.");
+                                ui.checkbox(&mut self.config.show_load_timings, "Show page load timings")
+                                    .on_hover_text("Debug overlay with the time spent decoding, previewing, resizing, filtering and uploading the pages on screen.");
                                 ui.add_space(20.0);
 
                                 egui::CollapsingHeader::new(egui::RichText::new("Key Config").size(20.0).strong())
@@ -1086,6 +3757,78 @@ impl eframe::App for MangaReader {
                                             ui.label("Odd/Even Page Start:");
                                             render_binding_button(ui, "View Mode", &mut self.config.keys.view_mode, &mut self.binding_action);
                                             ui.end_row();
+                                            ui.label("Insert/Remove Blank Page:");
+                                            render_binding_button(ui, "Toggle Blank Page", &mut self.config.keys.toggle_blank_page, &mut self.binding_action);
+                                            ui.end_row();
+                                            ui.label("Toggle Auto Crop:");
+                                            render_binding_button(ui, "Toggle Auto Crop", &mut self.config.keys.toggle_auto_crop, &mut self.binding_action);
+                                            ui.end_row();
+                                            ui.label("Toggle Night Mode:");
+                                            render_binding_button(ui, "Toggle Night Mode", &mut self.config.keys.toggle_night_mode, &mut self.binding_action);
+                                            ui.end_row();
+                                            ui.label("Pan Up:");
+                                            render_binding_button(ui, "Pan Up", &mut self.config.keys.pan_up, &mut self.binding_action);
+                                            ui.end_row();
+                                            ui.label("Pan Down:");
+                                            render_binding_button(ui, "Pan Down", &mut self.config.keys.pan_down, &mut self.binding_action);
+                                            ui.end_row();
+                                            ui.label("Pan Left:");
+                                            render_binding_button(ui, "Pan Left", &mut self.config.keys.pan_left, &mut self.binding_action);
+                                            ui.end_row();
+                                            ui.label("Pan Right:");
+                                            render_binding_button(ui, "Pan Right", &mut self.config.keys.pan_right, &mut self.binding_action);
+                                            ui.end_row();
+                                            ui.label("Zoom In:");
+                                            render_binding_button(ui, "Zoom In", &mut self.config.keys.zoom_in, &mut self.binding_action);
+                                            ui.end_row();
+                                            ui.label("Zoom Out:");
+                                            render_binding_button(ui, "Zoom Out", &mut self.config.keys.zoom_out, &mut self.binding_action);
+                                            ui.end_row();
+                                            ui.label("Reset Zoom:");
+                                            render_binding_button(ui, "Reset Zoom", &mut self.config.keys.reset_zoom, &mut self.binding_action);
+                                            ui.end_row();
+                                            ui.label("Toggle Panel View:");
+                                            render_binding_button(ui, "Toggle Panel View", &mut self.config.keys.toggle_panel_view, &mut self.binding_action);
+                                            ui.end_row();
+                                            ui.label("Page Overview:");
+                                            render_binding_button(ui, "Page Overview", &mut self.config.keys.toggle_page_overview, &mut self.binding_action);
+                                            ui.end_row();
+                                            ui.label("Library:");
+                                            render_binding_button(ui, "Library", &mut self.config.keys.toggle_library, &mut self.binding_action);
+                                            ui.end_row();
+                                            ui.label("Reopen Last:");
+                                            render_binding_button(ui, "Reopen Last", &mut self.config.keys.reopen_last, &mut self.binding_action);
+                                            ui.end_row();
+                                            ui.label("Toggle Bookmark:");
+                                            render_binding_button(ui, "Toggle Bookmark", &mut self.config.keys.toggle_bookmark, &mut self.binding_action);
+                                            ui.end_row();
+                                            ui.label("Next Bookmark:");
+                                            render_binding_button(ui, "Next Bookmark", &mut self.config.keys.next_bookmark, &mut self.binding_action);
+                                            ui.end_row();
+                                            ui.label("Prev Bookmark:");
+                                            render_binding_button(ui, "Prev Bookmark", &mut self.config.keys.prev_bookmark, &mut self.binding_action);
+                                            ui.end_row();
+                                            ui.label("Next Unread File:");
+                                            render_binding_button(ui, "Next Unread", &mut self.config.keys.next_unread, &mut self.binding_action);
+                                            ui.end_row();
+                                            ui.label("Move Book:");
+                                            render_binding_button(ui, "Move Book", &mut self.config.keys.move_book, &mut self.binding_action);
+                                            ui.end_row();
+                                            ui.label("Copy Book:");
+                                            render_binding_button(ui, "Copy Book", &mut self.config.keys.copy_book, &mut self.binding_action);
+                                            ui.end_row();
+                                            ui.label("Trash Book:");
+                                            render_binding_button(ui, "Trash Book", &mut self.config.keys.trash_book, &mut self.binding_action);
+                                            ui.end_row();
+                                            ui.label("Rename Book:");
+                                            render_binding_button(ui, "Rename Book", &mut self.config.keys.rename_book, &mut self.binding_action);
+                                            ui.end_row();
+                                            ui.label("Undo Move/Trash:");
+                                            render_binding_button(ui, "Undo Curation", &mut self.config.keys.undo_curation, &mut self.binding_action);
+                                            ui.end_row();
+                                            ui.label("Magnifier (hold):");
+                                            render_binding_button(ui, "Magnifier", &mut self.config.keys.magnifier, &mut self.binding_action);
+                                            ui.end_row();
                                             ui.label("Open File:");
                                             render_binding_button(ui, "Open File", &mut self.config.keys.open_file, &mut self.binding_action);
                                             ui.end_row();
@@ -1163,7 +3906,7 @@ impl eframe::App for MangaReader {
 
         if self.config.show_top_bar && !self.is_fullscreen {
             egui::TopBottomPanel::top("top_toolbar")
-                .frame(egui::Frame::NONE.fill(egui::Color32::from_gray(30)).inner_margin(4.0))
+                .frame(egui::Frame::NONE.fill(if self.config.night_mode { egui::Color32::from_gray(12) } else { egui::Color32::from_gray(30) }).inner_margin(4.0))
                 .show(ctx, |ui| {
                     ui.horizontal(|ui| {
                         // --- Folder Navigation ---
@@ -1181,7 +3924,7 @@ impl eframe::App for MangaReader {
                         if ui.button("◀").on_hover_text("Prev Page").clicked() { self.prev_page(ctx); }
 
                         // Page Indicator in middle
-                        ui.label(format!("{} / {}", self.current_index + 1, self.image_files.len()));
+                        ui.label(format!("{} / {}", self.current_index + 1, self.pages.len()));
 
                         if ui.button("▶").on_hover_text("Next Page").clicked() { self.next_page(ctx); }
                         if ui.button("⏭").on_hover_text("Last Page").clicked() { self.go_to_last_page(ctx); }
@@ -1192,6 +3935,18 @@ impl eframe::App for MangaReader {
                         if ui.button(shift_label).clicked() {
                             self.change_shifted_mode(ctx);
                         }
+                        if ui.button("⬜").on_hover_text("Insert/Remove Blank Page").clicked() {
+                            self.toggle_blank_page(ctx);
+                        }
+                        if ui.selectable_label(self.panel_view, "Panels").on_hover_text("Panel by Panel View").clicked() {
+                            self.set_panel_view(!self.panel_view, ctx);
+                        }
+                        if ui.selectable_label(self.page_overview, "Pages").on_hover_text("Page Overview").clicked() {
+                            self.set_page_overview(!self.page_overview);
+                        }
+                        if ui.selectable_label(self.show_bookmarks, "🔖").on_hover_text("Bookmarks").clicked() {
+                            self.show_bookmarks = !self.show_bookmarks;
+                        }
 
                         if ui.button("📺").on_hover_text("Toggle Fullscreen").clicked() {
                             self.is_fullscreen = !self.is_fullscreen;
@@ -1199,12 +3954,32 @@ impl eframe::App for MangaReader {
                         }
                         ui.separator();
                         if ui.button("Open File").clicked() { self.open_file_dialog(); }
+                        ui.menu_button("Recent", |ui| {
+                            if self.config.recent_files.is_empty() {
+                                ui.label("No recent files");
+                            }
+                            if let Some(path) = self.recent_files_list(ui) {
+                                ui.close();
+                                self.load_source(path, ctx);
+                            }
+                            if !self.config.recent_files.is_empty() {
+                                ui.separator();
+                                if ui.button("Clear Recent Files").clicked() {
+                                    self.config.recent_files.clear();
+                                    self.save_settings();
+                                    ui.close();
+                                }
+                            }
+                        });
+                        if ui.selectable_label(self.show_library, "Library").clicked() {
+                            self.set_library(!self.show_library, ctx);
+                        }
 
                         ui.separator();
                         // --- THE SLIDER ---
                         // We use a 1-based slider for better user experience
-                        let mut page_val = self.current_index + 1;
-                        let max_pages = self.image_files.len().max(1);
+                        let mut page_val = self.scrub_target.unwrap_or(self.current_index) + 1;
+                        let max_pages = self.pages.len().max(1);
 
                         // ui.available_width() ensures the slider stretches to fill the gap
                         let slider_width = ui.available_width() / 3.0; // Reserve space for right-side buttons
@@ -1218,10 +3993,19 @@ impl eframe::App for MangaReader {
                                 .text(format!("/ {}", max_pages))
                         );
                         self.is_scrubbing = slider.dragged();
-                        if slider.changed() {
-                            self.current_index = page_val - 1;
-                            self.reset_buffer();
-                            self.textures = self.load_pair(self.current_index, ctx);
+                        self.paint_bookmark_ticks(ui.painter(), Rect::from_min_size(slider.rect.min, egui::vec2(slider_width, slider.rect.height())));
+                        let target = page_val - 1;
+                        if slider.dragged() {
+                            // Only thumbnails while dragging, the page is loaded on release
+                            self.scrub_target = Some(target);
+                            self.show_scrub_preview(ctx, slider.rect, target);
+                        } else {
+                            self.scrub_target = None;
+                            if target != self.current_index && (slider.changed() || slider.drag_stopped()) {
+                                self.current_index = target;
+                                self.reset_buffer();
+                                self.textures = self.load_pair_progressive(self.current_index, ctx);
+                            }
                         }
 
                         // --- Hide Button ---
@@ -1234,58 +4018,38 @@ impl eframe::App for MangaReader {
                 });
         }
 
+        let background = if self.config.night_mode { egui::Color32::from_gray(5) } else { egui::Color32::from_gray(40) };
         egui::CentralPanel::default()
-            .frame(egui::Frame::NONE.fill(egui::Color32::from_gray(40)))
+            .frame(egui::Frame::NONE.fill(background))
             .show(ctx, |ui| {
                 let rect = ui.available_rect_before_wrap();
 
                 // Create a 'Response' for the entire background area first,
                 // but we check it at the END of the code.
                 let bg_response = ui.interact(rect, ui.id().with("bg"), egui::Sense::click());
-
-                if self.zip_path.is_some() {
-                    // Show single image on center or if in shifted mode or is zoomed
-                    let is_zoomed = (self.zoom_factor - 1.0).abs() > 0.01;
-                    let viewing_single = self.is_single_page() || (self.is_shifted && self.current_index == 0) || is_zoomed;
-
-                    if viewing_single {
-                        // Wrap in ScrollArea for panning/dragging
-                        egui::ScrollArea::both()
-                            .auto_shrink([false; 2])
-                            .drag_to_scroll(true) // This enables the "drag the image" feature
-                            .show(ui, |ui| {
-                                if let Some(tex) = &self.textures[0] {
-                                    let tex_size = tex.size_vec2();
-                                    let aspect_ratio = tex_size.x / tex_size.y;
-
-                                    // Calculate size based on zoom
-                                    // 1.0 zoom = screen height. 2.0 zoom = double screen height.
-                                    let zoom_height = rect.height() * self.zoom_factor;
-                                    let zoom_width = zoom_height * aspect_ratio;
-                                    let zoom_size = egui::vec2(zoom_width, zoom_height);
-
-                                    let layout = egui::Layout::centered_and_justified(Direction::TopDown);
-                                    ui.with_layout(layout, |ui| {
-                                        ui.add(egui::Image::new(tex)
-                                            .fit_to_exact_size(zoom_size)
-                                            .maintain_aspect_ratio(true));
-                                    });
-                                }
-                            });
-                        let resp = ui.interact(rect, ui.id().with("cover_hit"), egui::Sense::click());
-                        if resp.clicked() {
-                            self.next_page(ctx);
-                        }
-                        if resp.clicked_by(PointerButton::Secondary) {
-                            self.prev_page(ctx);
-                        }
+                self.page_rects = [None, None];
+
+                if self.show_library {
+                    self.show_library_view(ui, rect, ctx);
+                } else if self.zip_path.is_some() {
+                    // Show single image on center or if in shifted mode, zoomed pages can be panned
+                    let viewing_single = self.is_single_page() || (self.is_shifted && self.current_index == 0);
+
+                    if self.page_overview {
+                        self.show_page_overview(ui, rect, ctx);
+                    } else if self.panel_view {
+                        self.show_panel_view(ui, rect, ctx);
+                    } else if viewing_single || self.is_zoomed() {
+                        self.show_scroll_view(ui, rect, viewing_single, ctx);
                     } else {
+                        self.view_rect = rect;
+                        self.scroll_offset = egui::Vec2::ZERO;
                         let center = rect.center().x;
                         let mut left_half = egui::Rect::from_min_max(rect.min, egui::pos2(center, rect.max.y));
                         let mut right_half = egui::Rect::from_min_max(egui::pos2(center, rect.min.y), rect.max);
                         let mut align_for_left_side: Align = egui::Align::RIGHT;
                         let mut align_for_right_side: Align = egui::Align::LEFT;
-                        if self.config.page_view_options == PageViewOptions::DoubleLR {
+                        if self.page_view() == PageViewOptions::DoubleLR {
                             std::mem::swap(&mut left_half, &mut right_half);
                             align_for_left_side = egui::Align::LEFT;
                             align_for_right_side = egui::Align::RIGHT;
@@ -1307,20 +4071,39 @@ impl eframe::App for MangaReader {
                     }
                 } else {
                     // the start screen
-                    ui.centered_and_justified(|ui| {
-                        let start_btn = egui::Button::new(
-                            egui::RichText::new("Click anywhere to open a Zip file")
-                                .size(20.0)
-                                .color(egui::Color32::from_gray(200))
-                        ).fill(egui::Color32::from_gray(40));
-                        if ui.add_sized(ctx.content_rect().size(), start_btn).clicked() {
-                            self.open_file_dialog();
-                        }
-                    });
+                    if self.config.recent_files.is_empty() {
+                        ui.centered_and_justified(|ui| {
+                            let start_btn = egui::Button::new(
+                                egui::RichText::new("Click anywhere to open a Zip file")
+                                    .size(20.0)
+                                    .color(egui::Color32::from_gray(200))
+                            ).fill(egui::Color32::from_gray(40));
+                            if ui.add_sized(ctx.content_rect().size(), start_btn).clicked() {
+                                self.open_file_dialog();
+                            }
+                        });
+                    } else {
+                        ui.vertical_centered(|ui| {
+                            ui.add_space(rect.height() * 0.15);
+                            ui.label(egui::RichText::new("Recent Files").size(24.0).strong().color(egui::Color32::from_gray(200)));
+                            ui.add_space(10.0);
+                            let recent = egui::ScrollArea::vertical()
+                                .max_height(rect.height() * 0.5)
+                                .show(ui, |ui| self.recent_files_list(ui))
+                                .inner;
+                            if let Some(path) = recent {
+                                self.load_source(path, ctx);
+                            }
+                            ui.add_space(20.0);
+                            if ui.button(egui::RichText::new("Open File...").size(20.0)).clicked() {
+                                self.open_file_dialog();
+                            }
+                        });
+                    }
                 }
 
                 // --- THE BACKGROUND CLICK CHECK (When Zip is Open) ---
-                if self.zip_path.is_some() && bg_response.clicked() {
+                if self.zip_path.is_some() && !self.page_overview && !self.show_library && bg_response.clicked() {
                     // Check if the click was actually handled by an image
                     if !ctx.is_using_pointer() {
                         // Check coordinates to ensure we aren't inside the "reading zones"
@@ -1378,6 +4161,11 @@ impl eframe::App for MangaReader {
                                     .color(egui::Color32::from_white_alpha((255.0 * opacity) as u8))
                                     .size(24.0)
                                     .strong());
+                                if let Some(badge) = self.book_badge() {
+                                    ui.label(egui::RichText::new(badge)
+                                        .color(egui::Color32::from_white_alpha((200.0 * opacity) as u8))
+                                        .size(16.0));
+                                }
                             });
                         ctx.request_repaint(); // Keep the animation smooth
                     } else {
@@ -1401,7 +4189,7 @@ impl eframe::App for MangaReader {
                             .show(ctx, |ui| {
                                 // Ensure text stays on one line
                                 ui.horizontal(|ui| {
-                                    let page_text = format!("{} / {}", self.current_index + 1, self.image_files.len());
+                                    let page_text = format!("{} / {}", self.current_index + 1, self.pages.len());
                                     ui.label(egui::RichText::new(page_text)
                                         .color(egui::Color32::from_white_alpha((200.0 * opacity) as u8))
                                         .size(22.0) // Much larger font
@@ -1415,10 +4203,130 @@ impl eframe::App for MangaReader {
                 }
             });
 
+        self.show_loupe(ctx);
+
+        if self.config.show_load_timings && self.zip_path.is_some() {
+            let timings = self.load_timings;
+            egui::Area::new(egui::Id::new("load_timings"))
+                .anchor(egui::Align2::LEFT_BOTTOM, [10.0, -10.0])
+                .show(ctx, |ui| {
+                    egui::Frame::NONE.fill(egui::Color32::from_black_alpha(180)).inner_margin(6.0).corner_radius(5.0).show(ui, |ui| {
+                        let ms = |d: Duration| format!("{:>7.1} ms", d.as_secs_f32() * 1000.0);
+                        let total = timings.decode + timings.preview + timings.resize + timings.filters + timings.upload;
+                        let text = format!(
+                            "decode  {}\npreview {}\nresize  {}\nfilters {}\nupload  {}\ntotal   {}",
+                            ms(timings.decode), ms(timings.preview), ms(timings.resize), ms(timings.filters), ms(timings.upload), ms(total),
+                        );
+                        ui.label(egui::RichText::new(text).monospace().color(egui::Color32::from_gray(220)));
+                    });
+                });
+        }
+
+        if self.panel_view && !self.page_overview && !self.show_library && self.zip_path.is_some() {
+            egui::Area::new(egui::Id::new("panel_controls"))
+                .anchor(egui::Align2::CENTER_BOTTOM, [0.0, -20.0])
+                .show(ctx, |ui| {
+                    ui.horizontal(|ui| {
+                        let edit_text = if self.panel_edit { "Done" } else { "Edit Panels" };
+                        if ui.button(edit_text).on_hover_text("Drag to draw a panel, right click a panel to remove it.").clicked() {
+                            self.panel_edit = !self.panel_edit;
+                            self.panel_drag_start = None;
+                        }
+                        if self.panel_edit && ui.button("Reset Page Panels").on_hover_text("Go back to the detected panels of this page.").clicked() {
+                            self.reset_page_panels();
+                        }
+                        if ui.button("Exit Panel View").clicked() {
+                            self.set_panel_view(false, ctx);
+                        }
+                    });
+                });
+        }
+
+        self.show_resume_prompt(ctx);
+        self.show_bookmark_list(ctx);
+        self.show_target_picker(ctx);
+        self.show_rename_box(ctx);
+        self.show_trash_confirm(ctx);
+        self.show_curation_notice(ctx);
+
         // Keep preloading buffers
         self.update_buffers(ctx);
+        self.evict_tiles();
+        self.update_history();
+        self.update_session(ctx);
     }
     fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
         self.save_settings();
+        self.book_db.save();
+        self.save_session();
+    }
+}
+
+/// Find which images of the current source are landscape double page scans.
+/// Only the image headers are read when the format allows it.
+fn scan_spreads(mode: SourceMode, source_path: &Path, image_files: &[String]) -> Vec<bool> {
+    let mut spreads = vec![false; image_files.len()];
+
+    match mode {
+        SourceMode::Folder => {
+            for (i, filename) in image_files.iter().enumerate() {
+                if let Ok((w, h)) = image::image_dimensions(filename) {
+                    spreads[i] = imaging::is_spread(w, h);
+                }
+            }
+        }
+        SourceMode::Zip => {
+            // Image headers are at the start of the file, try a small chunk before reading it all
+            const HEADER_PROBE_SIZE: u64 = 256 * 1024;
+            if let Some(mut arc) = File::open(source_path).ok().and_then(|f| zip::ZipArchive::new(f).ok()) {
+                for (i, filename) in image_files.iter().enumerate() {
+                    let mut head = Vec::new();
+                    if let Ok(f) = arc.by_name(filename) {
+                        let _ = f.take(HEADER_PROBE_SIZE).read_to_end(&mut head);
+                    }
+                    let mut size = imaging::image_dimensions(&head);
+                    if size.is_none() {
+                        if let Ok(mut f) = arc.by_name(filename) {
+                            head.clear();
+                            let _ = f.read_to_end(&mut head);
+                            size = imaging::image_dimensions(&head);
+                        }
+                    }
+                    if let Some((w, h)) = size {
+                        spreads[i] = imaging::is_spread(w, h);
+                    }
+                }
+            }
+        }
+        SourceMode::Rar => {
+            let indices: std::collections::HashMap<&str, usize> = image_files.iter()
+                .enumerate()
+                .map(|(i, name)| (name.as_str(), i))
+                .collect();
+            if let Ok(rar_archive) = unrar::Archive::new(source_path).open_for_processing() {
+                let mut cursor = rar_archive.read_header().ok().flatten();
+                while let Some(e) = cursor {
+                    let index = e.entry().filename.to_str().and_then(|name| indices.get(name).copied());
+                    cursor = match index {
+                        Some(i) => e.read().ok().and_then(|(bytes, arc)| {
+                            if let Some((w, h)) = imaging::image_dimensions(&bytes) {
+                                spreads[i] = imaging::is_spread(w, h);
+                            }
+                            arc.read_header().ok().flatten()
+                        }),
+                        None => e.skip().ok().and_then(|arc| arc.read_header().ok().flatten()),
+                    };
+                }
+            }
+        }
+        SourceMode::Pdf => {
+            let pdfium = pdfium_render::prelude::Pdfium::default();
+            if let Ok(doc) = pdfium.load_pdf_from_file(source_path, None) {
+                for (i, page) in doc.pages().iter().enumerate().take(spreads.len()) {
+                    spreads[i] = page.width().value > page.height().value;
+                }
+            }
+        }
     }
+    spreads
 }
@@ -0,0 +1,276 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+use crate::config::PageViewOptions;
+use crate::utils::{exe_dir_file, write_atomic};
+
+const BOOK_DB_FILE: &str = "book_db.json";
+
+/// Identity of a book that survives restarts: its path, the same key the book settings use.
+/// An archive that is written again, like after editing its ComicInfo, stays the same book.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct BookId {
+    pub path: String,
+}
+
+impl BookId {
+    pub fn of(path: &Path) -> Self {
+        Self { path: path.to_string_lossy().to_string() }
+    }
+}
+
+/// Where the reading of a book stopped
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct ReadingHistory {
+    /// Index in the page list, blank and split pages included
+    pub last_page: usize,
+    pub page_count: usize,
+    pub is_shifted: bool,
+    pub view_mode: Option<PageViewOptions>,
+    /// Seconds since the epoch
+    pub first_opened: u64,
+    pub last_read: u64,
+    /// The last page was on screen at some point
+    pub finished: bool,
+}
+
+impl ReadingHistory {
+    /// Part of the book read so far, 0.0 to 1.0
+    pub fn progress(&self) -> f32 {
+        if self.page_count == 0 { 0.0 } else { (self.last_page + 1) as f32 / self.page_count as f32 }
+    }
+}
+
+#[derive(Debug, PartialEq, Copy, Clone, Serialize, Deserialize)]
+pub enum ReadStatus {
+    Unread,
+    InProgress,
+    Finished,
+}
+
+impl ReadStatus {
+    pub fn label(self) -> &'static str {
+        match self {
+            ReadStatus::Unread => "Unread",
+            ReadStatus::InProgress => "In Progress",
+            ReadStatus::Finished => "Finished",
+        }
+    }
+}
+
+/// A marked page. Pages are stored by image name, so inserted blank pages, split spreads,
+/// another sort order or pages arriving in a watched folder don't move the bookmark.
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct Bookmark {
+    /// Name of the image inside the book, see `BookRecord::book_entry`
+    pub entry: String,
+    /// Index of the image in the open book, found from `entry` by `locate_bookmarks`.
+    /// `None` while the image is missing.
+    pub file_index: Option<usize>,
+    pub label: String,
+    /// Seconds since the epoch
+    pub created: u64,
+}
+
+/// Bookmarks of a book as shared with others. The entry name finds the page
+/// in a copy of the book even when its images are numbered differently.
+#[derive(Serialize, Deserialize)]
+pub struct BookmarkExport {
+    pub book: String,
+    pub bookmarks: Vec<ExportedBookmark>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ExportedBookmark {
+    /// Image number, from 1
+    pub page: usize,
+    pub entry: String,
+    #[serde(default)]
+    pub label: String,
+}
+
+/// Everything remembered about one book
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct BookRecord {
+    /// Path of the book when it was last opened
+    pub path: String,
+    pub history: Option<ReadingHistory>,
+    /// Sorted by image index, missing images last
+    pub bookmarks: Vec<Bookmark>,
+    /// Status set by hand, otherwise it follows the reading history
+    pub status: Option<ReadStatus>,
+    /// 1 to 5 stars
+    pub rating: Option<u8>,
+    pub tags: Vec<String>,
+}
+
+impl BookRecord {
+    pub fn read_status(&self) -> ReadStatus {
+        self.status.unwrap_or(match &self.history {
+            None => ReadStatus::Unread,
+            Some(history) if history.finished => ReadStatus::Finished,
+            Some(_) => ReadStatus::InProgress,
+        })
+    }
+
+    /// JSON with the bookmarks of the book, `image_files` is its image list
+    pub fn export_bookmarks(&self, image_files: &[String]) -> String {
+        let export = BookmarkExport {
+            book: Path::new(&self.path).file_name().unwrap_or_default().to_string_lossy().to_string(),
+            bookmarks: self.bookmarks.iter().filter_map(|bookmark| {
+                let file_index = bookmark.file_index?;
+                Some(ExportedBookmark {
+                    page: file_index + 1,
+                    entry: entry_name(image_files.get(file_index)?),
+                    label: bookmark.label.clone(),
+                })
+            }).collect(),
+        };
+        serde_json::to_string_pretty(&export).unwrap_or_default()
+    }
+
+    /// Add the bookmarks of an export to the book, returns how many were new
+    pub fn import_bookmarks(&mut self, json: &str, image_files: &[String]) -> Result<usize, String> {
+        let export: BookmarkExport = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        let now = now_secs();
+        let mut added = 0;
+        for exported in export.bookmarks {
+            let file_index = image_files.iter()
+                .position(|name| entry_name(name) == exported.entry)
+                .or_else(|| exported.page.checked_sub(1).filter(|&i| i < image_files.len()));
+            let Some(file_index) = file_index else { continue };
+            if self.bookmarks.iter().any(|b| b.file_index == Some(file_index)) {
+                continue;
+            }
+            let entry = self.book_entry(&image_files[file_index]);
+            self.bookmarks.push(Bookmark { entry, file_index: Some(file_index), label: exported.label, created: now });
+            added += 1;
+        }
+        self.sort_bookmarks();
+        Ok(added)
+    }
+
+    /// Add or remove the bookmark of an image, returns true when it was added
+    pub fn toggle_bookmark(&mut self, file_index: usize, image_files: &[String]) -> bool {
+        if let Some(i) = self.bookmarks.iter().position(|b| b.file_index == Some(file_index)) {
+            self.bookmarks.remove(i);
+            return false;
+        }
+        let Some(name) = image_files.get(file_index) else { return false };
+        let entry = self.book_entry(name);
+        self.bookmarks.push(Bookmark { entry, file_index: Some(file_index), label: String::new(), created: now_secs() });
+        self.sort_bookmarks();
+        true
+    }
+
+    /// Find the bookmarked images in `image_files` by their name. Bookmarks of images that
+    /// are gone stay, they come back when the image does.
+    pub fn locate_bookmarks(&mut self, image_files: &[String]) {
+        let entries: Vec<String> = image_files.iter().map(|name| self.book_entry(name)).collect();
+        for bookmark in &mut self.bookmarks {
+            // Saved before bookmarks kept the image name
+            if bookmark.entry.is_empty() {
+                if let Some(entry) = bookmark.file_index.and_then(|i| entries.get(i)) {
+                    bookmark.entry.clone_from(entry);
+                }
+            }
+            bookmark.file_index = entries.iter().position(|entry| *entry == bookmark.entry);
+        }
+        self.sort_bookmarks();
+    }
+
+    /// Name of an image inside the book: the entry of an archive, or the file name in a folder,
+    /// whose images are listed with the folder path in front
+    fn book_entry(&self, name: &str) -> String {
+        Path::new(name).strip_prefix(&self.path).map_or(name.to_string(), |rel| rel.to_string_lossy().to_string())
+    }
+
+    fn sort_bookmarks(&mut self) {
+        self.bookmarks.sort_by_key(|b| b.file_index.unwrap_or(usize::MAX));
+    }
+}
+
+/// Name of an image without the folder it is in, which differs between copies of a folder source
+fn entry_name(name: &str) -> String {
+    Path::new(name).file_name().map_or(name.to_string(), |n| n.to_string_lossy().to_string())
+}
+
+/// Local database of the books, keyed by their identity
+#[derive(Serialize, Deserialize, Default)]
+pub struct BookDb {
+    books: HashMap<String, BookRecord>,
+    #[serde(skip)]
+    dirty: bool,
+}
+
+impl BookDb {
+    pub fn load() -> Self {
+        let mut db: Self = std::fs::read_to_string(exe_dir_file(BOOK_DB_FILE))
+            .ok()
+            .and_then(|data| serde_json::from_str(&data).ok())
+            .unwrap_or_default();
+        db.merge_old_keys();
+        db
+    }
+
+    /// Records used to be keyed by "path|size|mtime", and every rewrite of an archive left
+    /// the old one behind. Keep the most recently read record of each path.
+    fn merge_old_keys(&mut self) {
+        if self.books.iter().all(|(key, record)| *key == record.path) {
+            return;
+        }
+        let last_read = |record: &BookRecord| record.history.as_ref().map_or(0, |history| history.last_read);
+        for (_, record) in std::mem::take(&mut self.books) {
+            if self.books.get(&record.path).is_none_or(|kept| last_read(kept) < last_read(&record)) {
+                self.books.insert(record.path.clone(), record);
+            }
+        }
+        self.dirty = true;
+    }
+
+    /// Write the database if something changed since the last save
+    pub fn save(&mut self) {
+        if !self.dirty {
+            return;
+        }
+        if let Ok(json) = serde_json::to_string_pretty(self) {
+            if write_atomic(&exe_dir_file(BOOK_DB_FILE), json.as_bytes()).is_ok() {
+                self.dirty = false;
+            }
+        }
+    }
+
+    pub fn get(&self, id: &BookId) -> Option<&BookRecord> {
+        self.books.get(&id.path)
+    }
+
+    pub fn read_status(&self, id: &BookId) -> ReadStatus {
+        self.get(id).map_or(ReadStatus::Unread, |record| record.read_status())
+    }
+
+    /// Carry the record of a book over to its new place after it was moved or renamed
+    pub fn move_record(&mut self, from: &BookId, to: &BookId) {
+        if let Some(mut record) = self.books.remove(&from.path) {
+            record.path.clone_from(&to.path);
+            self.books.insert(to.path.clone(), record);
+            self.dirty = true;
+        }
+    }
+
+    /// Record of a book to change, created when missing. Changes are written on the next `save`.
+    pub fn record_mut(&mut self, id: &BookId) -> &mut BookRecord {
+        self.dirty = true;
+        let record = self.books.entry(id.path.clone()).or_default();
+        record.path.clone_from(&id.path);
+        record
+    }
+}
+
+/// Seconds since the epoch
+pub fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs())
+}
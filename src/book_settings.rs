@@ -0,0 +1,70 @@
+use std::collections::HashMap;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use crate::config::{CropMargins, ImageAdjustments};
+use crate::panels::PanelRect;
+use crate::utils::exe_dir_file;
+
+const BOOK_SETTINGS_FILE: &str = "book_settings.json";
+
+/// Settings remembered for a single source (zip, rar, pdf or folder)
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct BookSettings {
+    /// Virtual blank pages, stored as the image index they are inserted before
+    pub blank_pages: Vec<usize>,
+    /// Override of the global "split double page scans" option
+    pub split_spreads: Option<bool>,
+    /// Manual crop used instead of the automatic margin detection
+    pub crop_override: Option<CropMargins>,
+    /// Image adjustments used instead of the global ones
+    pub adjustments: Option<ImageAdjustments>,
+    /// Hand corrected panels, keyed by page (the texture cache name). They are placed on the
+    /// whole page before cropping, so a new crop doesn't move them.
+    pub panels: HashMap<String, Vec<PanelRect>>,
+}
+
+/// All per-book settings, keyed by the source path
+#[derive(Serialize, Deserialize, Default)]
+pub struct BookSettingsStore {
+    books: HashMap<String, BookSettings>,
+}
+
+impl BookSettingsStore {
+    pub fn load() -> Self {
+        std::fs::read_to_string(exe_dir_file(BOOK_SETTINGS_FILE))
+            .ok()
+            .and_then(|data| serde_json::from_str(&data).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        if let Ok(json) = serde_json::to_string_pretty(self) {
+            let _ = std::fs::write(exe_dir_file(BOOK_SETTINGS_FILE), json);
+        }
+    }
+
+    pub fn get(&self, path: &Path) -> BookSettings {
+        self.books.get(&path.to_string_lossy().to_string()).cloned().unwrap_or_default()
+    }
+
+    /// Carry the settings of a book over to its new place after it was moved or renamed.
+    /// Written on the next `save`.
+    pub fn move_entry(&mut self, from: &Path, to: &Path) {
+        if let Some(settings) = self.books.remove(&from.to_string_lossy().to_string()) {
+            self.books.insert(to.to_string_lossy().to_string(), settings);
+        }
+    }
+
+    /// Store the settings of a book and write them to disk right away
+    pub fn set(&mut self, path: &Path, settings: BookSettings) {
+        let key = path.to_string_lossy().to_string();
+        // Don't keep entries that only hold default values
+        if settings == BookSettings::default() {
+            self.books.remove(&key);
+        } else {
+            self.books.insert(key, settings);
+        }
+        self.save();
+    }
+}
@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+use std::path::Path;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, PartialEq, Clone, Copy)]
@@ -22,6 +24,92 @@ pub enum LastPageAction {
     Nothing,
 }
 
+#[derive(Debug, PartialEq, Copy, Clone, Serialize, Deserialize)]
+pub enum NightColorPages {
+    Keep,       // leave colour pages untouched
+    WarmTint,   // tint colour pages toward warm light
+}
+
+#[derive(Debug, PartialEq, Copy, Clone, Serialize, Deserialize)]
+pub enum ResumeMode {
+    Resume,     // continue at the last page read
+    Ask,        // open at the first page and offer to continue
+    StartOver,  // always open at the first page
+}
+
+#[derive(Debug, PartialEq, Copy, Clone, Serialize, Deserialize)]
+pub enum LibrarySort {
+    Title,
+    Modified,
+    PageCount,
+}
+
+/// What the files and folders next to each other, and the pages of a folder, are ordered by
+#[derive(Debug, PartialEq, Copy, Clone, Serialize, Deserialize)]
+pub enum SortKey {
+    /// Series, volume and chapter read from the name
+    ParsedName,
+    NameNatural,
+    NameLexical,
+    Modified,
+    Created,
+    Size,
+    /// Volume, then number, from the ComicInfo.xml of the book
+    ComicInfo,
+}
+
+impl SortKey {
+    pub const ALL: [SortKey; 7] = [
+        SortKey::ParsedName,
+        SortKey::NameNatural,
+        SortKey::NameLexical,
+        SortKey::Modified,
+        SortKey::Created,
+        SortKey::Size,
+        SortKey::ComicInfo,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SortKey::ParsedName => "Volume/Chapter in Name",
+            SortKey::NameNatural => "Name (Natural)",
+            SortKey::NameLexical => "Name (Lexical)",
+            SortKey::Modified => "Date Modified",
+            SortKey::Created => "Date Created",
+            SortKey::Size => "Size",
+            SortKey::ComicInfo => "ComicInfo Volume/Number",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Copy, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SortOrder {
+    pub key: SortKey,
+    pub descending: bool,
+}
+
+impl Default for SortOrder {
+    fn default() -> Self {
+        Self { key: SortKey::NameNatural, descending: false }
+    }
+}
+
+/// Order of the entries of a folder: one for all folders, and the folders set apart
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FileSorting {
+    pub default: SortOrder,
+    /// Keyed by folder path
+    pub folders: HashMap<String, SortOrder>,
+}
+
+impl FileSorting {
+    pub fn order_for(&self, folder: &Path) -> SortOrder {
+        self.folders.get(folder.to_string_lossy().as_ref()).copied().unwrap_or(self.default)
+    }
+}
+
 #[derive(Debug, PartialEq, Copy, Clone, Serialize, Deserialize)]
 pub enum ResizeMethod {
     None,       // Use original resolution
@@ -43,6 +131,64 @@ impl ResizeMethod {
     }
 }
 
+/// A recently opened source and where it was left
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RecentFile {
+    pub path: std::path::PathBuf,
+    pub page: usize,
+    pub page_count: usize,
+}
+
+/// Margins cut from each side of a page, as a fraction of the page size
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct CropMargins {
+    pub left: f32,
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+}
+
+impl CropMargins {
+    /// The margins as they are cut, no side takes more than 45% of the page
+    pub fn clamped(self) -> Self {
+        let clamp = |fraction: f32| fraction.clamp(0.0, 0.45);
+        CropMargins { left: clamp(self.left), top: clamp(self.top), right: clamp(self.right), bottom: clamp(self.bottom) }
+    }
+}
+
+/// Filters applied to every page after resizing
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct ImageAdjustments {
+    pub auto_levels: bool,
+    pub remove_tint: bool,
+    pub grayscale: bool,
+    pub brightness: f32, // -1.0 ..= 1.0
+    pub contrast: f32,   // -1.0 ..= 1.0
+    pub gamma: f32,      // 1.0 = unchanged
+    pub sharpen: f32,    // unsharp mask amount, 0.0 = off
+}
+
+impl Default for ImageAdjustments {
+    fn default() -> Self {
+        Self {
+            auto_levels: false,
+            remove_tint: false,
+            grayscale: false,
+            brightness: 0.0,
+            contrast: 0.0,
+            gamma: 1.0,
+            sharpen: 0.0,
+        }
+    }
+}
+
+impl ImageAdjustments {
+    pub fn is_neutral(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
 pub struct Shortcut {
     pub key: egui::Key,
@@ -84,9 +230,33 @@ pub enum MangaAction {
     ViewMode,
     OpenFile,
     QuitApp,
+    ToggleBlankPage,
+    ToggleAutoCrop,
+    ToggleNightMode,
+    PanUp,
+    PanDown,
+    PanLeft,
+    PanRight,
+    ZoomIn,
+    ZoomOut,
+    ResetZoom,
+    TogglePanelView,
+    TogglePageOverview,
+    ToggleLibrary,
+    ReopenLast,
+    ToggleBookmark,
+    NextBookmark,
+    PrevBookmark,
+    NextUnread,
+    MoveBook,
+    CopyBook,
+    TrashBook,
+    RenameBook,
+    UndoCuration,
 }
 
 #[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(default)]
 pub struct KeyConfig {
     pub next_page: Shortcut,
     pub prev_page: Shortcut,
@@ -100,6 +270,30 @@ pub struct KeyConfig {
     pub view_mode: Shortcut,
     pub open_file: Shortcut,
     pub quit_app: Shortcut,
+    pub toggle_blank_page: Shortcut,
+    pub toggle_auto_crop: Shortcut,
+    pub toggle_night_mode: Shortcut,
+    pub pan_up: Shortcut,
+    pub pan_down: Shortcut,
+    pub pan_left: Shortcut,
+    pub pan_right: Shortcut,
+    pub zoom_in: Shortcut,
+    pub zoom_out: Shortcut,
+    pub reset_zoom: Shortcut,
+    pub toggle_panel_view: Shortcut,
+    pub toggle_page_overview: Shortcut,
+    pub toggle_library: Shortcut,
+    pub reopen_last: Shortcut,
+    pub toggle_bookmark: Shortcut,
+    pub next_bookmark: Shortcut,
+    pub prev_bookmark: Shortcut,
+    pub next_unread: Shortcut,
+    pub move_book: Shortcut,
+    pub copy_book: Shortcut,
+    pub trash_book: Shortcut,
+    pub rename_book: Shortcut,
+    pub undo_curation: Shortcut,
+    pub magnifier: Shortcut,
 }
 
 impl Default for KeyConfig {
@@ -117,12 +311,37 @@ impl Default for KeyConfig {
             view_mode: Shortcut::new(egui::Key::Enter, false, false, false),
             open_file: Shortcut::new(egui::Key::O, false, false, false),
             quit_app: Shortcut::new(egui::Key::Escape, false, false, false),
+            toggle_blank_page: Shortcut::new(egui::Key::B, false, false, false),
+            toggle_auto_crop: Shortcut::new(egui::Key::C, false, false, false),
+            toggle_night_mode: Shortcut::new(egui::Key::N, false, false, false),
+            pan_up: Shortcut::new(egui::Key::W, false, false, false),
+            pan_down: Shortcut::new(egui::Key::S, false, false, false),
+            pan_left: Shortcut::new(egui::Key::A, false, false, false),
+            pan_right: Shortcut::new(egui::Key::D, false, false, false),
+            zoom_in: Shortcut::new(egui::Key::Plus, false, false, false),
+            zoom_out: Shortcut::new(egui::Key::Minus, false, false, false),
+            reset_zoom: Shortcut::new(egui::Key::Num0, false, false, false),
+            toggle_panel_view: Shortcut::new(egui::Key::G, false, false, false),
+            toggle_page_overview: Shortcut::new(egui::Key::P, false, false, false),
+            toggle_library: Shortcut::new(egui::Key::L, false, false, false),
+            reopen_last: Shortcut::new(egui::Key::T, true, false, true),
+            toggle_bookmark: Shortcut::new(egui::Key::D, true, false, false),
+            next_bookmark: Shortcut::new(egui::Key::CloseBracket, false, false, false),
+            prev_bookmark: Shortcut::new(egui::Key::OpenBracket, false, false, false),
+            next_unread: Shortcut::new(egui::Key::U, false, false, false),
+            move_book: Shortcut::new(egui::Key::M, true, false, false),
+            copy_book: Shortcut::new(egui::Key::M, true, false, true),
+            trash_book: Shortcut::new(egui::Key::Delete, false, false, false),
+            rename_book: Shortcut::new(egui::Key::F2, false, false, false),
+            undo_curation: Shortcut::new(egui::Key::Z, true, false, false),
+            magnifier: Shortcut::new(egui::Key::M, false, false, false),
         }
     }
 }
 
 
 #[derive(Serialize, Deserialize)]
+#[serde(default)]
 pub struct AppSettings {
     pub resize_method: ResizeMethod,
     pub page_view_options: PageViewOptions,
@@ -135,6 +354,36 @@ pub struct AppSettings {
     pub show_top_bar: bool,
     pub enable_auto_image_byte_fix: bool,
     pub last_page_action: LastPageAction,
+    pub split_spreads: bool,
+    pub auto_crop: bool,
+    pub auto_crop_tolerance: u8,
+    pub adjustments: ImageAdjustments,
+    pub night_mode: bool,
+    pub night_color_pages: NightColorPages,
+    pub night_warmth: f32,
+    pub night_schedule: bool,
+    pub night_start_hour: u32,
+    pub night_end_hour: u32,
+    pub guided_pan: bool,
+    pub pan_overlap: f32,
+    pub pan_animation_ms: u64,
+    pub loupe_size: f32,
+    pub loupe_magnification: f32,
+    pub resize_dither: bool,
+    pub show_load_timings: bool,
+    pub library_roots: Vec<std::path::PathBuf>,
+    pub library_sort: LibrarySort,
+    pub library_sort_descending: bool,
+    pub resume_mode: ResumeMode,
+    pub restore_session: bool,
+    pub recent_files: Vec<RecentFile>,
+    pub recent_files_max: usize,
+    pub skip_finished_files: bool,
+    /// Top folder for next/prev folder, they walk every folder below it
+    pub navigation_root: Option<std::path::PathBuf>,
+    pub file_sorting: FileSorting,
+    /// Folders offered by "Move Book" and "Copy Book", in the order of their number keys
+    pub curation_targets: Vec<std::path::PathBuf>,
 }
 
 impl Default for AppSettings {
@@ -151,6 +400,34 @@ impl Default for AppSettings {
             show_top_bar: true,
             enable_auto_image_byte_fix: true,
             last_page_action: LastPageAction::GotoNextFile,
+            split_spreads: false,
+            auto_crop: false,
+            auto_crop_tolerance: 24,
+            adjustments: ImageAdjustments::default(),
+            night_mode: false,
+            night_color_pages: NightColorPages::Keep,
+            night_warmth: 0.5,
+            night_schedule: false,
+            night_start_hour: 21,
+            night_end_hour: 7,
+            guided_pan: true,
+            pan_overlap: 0.1,
+            pan_animation_ms: 250,
+            loupe_size: 300.0,
+            loupe_magnification: 3.0,
+            resize_dither: false,
+            show_load_timings: false,
+            library_roots: Vec::new(),
+            library_sort: LibrarySort::Title,
+            library_sort_descending: false,
+            resume_mode: ResumeMode::Resume,
+            restore_session: false,
+            recent_files: Vec::new(),
+            recent_files_max: 10,
+            skip_finished_files: false,
+            navigation_root: None,
+            file_sorting: FileSorting::default(),
+            curation_targets: Vec::new(),
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,155 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// A change made to a book on disk, kept to take it back
+pub enum Curation {
+    /// Moved to another folder or renamed
+    Moved { from: PathBuf, to: PathBuf },
+    Copied { original: PathBuf, copy: PathBuf },
+    Trashed { original: PathBuf },
+}
+
+impl Curation {
+    /// Where the book was before, and is again after an undo
+    pub fn original(&self) -> &Path {
+        match self {
+            Curation::Moved { from, .. } => from,
+            Curation::Copied { original, .. } => original,
+            Curation::Trashed { original } => original,
+        }
+    }
+
+    /// "Moved to D:\Manga\Done"
+    pub fn describe(&self) -> String {
+        match self {
+            Curation::Moved { from, to } if from.parent() == to.parent() => {
+                format!("Renamed to {}", to.file_name().unwrap_or_default().to_string_lossy())
+            }
+            Curation::Moved { to, .. } => format!("Moved to {}", to.parent().unwrap_or(to).display()),
+            Curation::Copied { copy, .. } => format!("Copied to {}", copy.parent().unwrap_or(copy).display()),
+            Curation::Trashed { .. } => "Moved to the trash".to_string(),
+        }
+    }
+
+    pub fn undo(&self) -> Result<(), String> {
+        match self {
+            Curation::Moved { from, to } => move_book(to, from).map_err(|e| e.to_string()),
+            Curation::Copied { copy, .. } => remove(copy).map_err(|e| e.to_string()),
+            Curation::Trashed { original } => restore_from_trash(original),
+        }
+    }
+}
+
+/// Move or rename a book, a file or a folder. Across drives it is copied, then removed.
+pub fn move_book(source: &Path, dest: &Path) -> io::Result<()> {
+    if dest.exists() {
+        return Err(already_exists(dest));
+    }
+    match fs::rename(source, dest) {
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+            copy_book(source, dest)?;
+            remove(source)
+        }
+        result => result,
+    }
+}
+
+/// Copy a book, folders with everything in them
+pub fn copy_book(source: &Path, dest: &Path) -> io::Result<()> {
+    if dest.exists() {
+        return Err(already_exists(dest));
+    }
+    if source.is_dir() {
+        fs::create_dir(dest)?;
+        for entry in fs::read_dir(source)? {
+            let entry = entry?;
+            copy_book(&entry.path(), &dest.join(entry.file_name()))?;
+        }
+        Ok(())
+    } else {
+        fs::copy(source, dest).map(|_| ())
+    }
+}
+
+pub fn trash_book(path: &Path) -> Result<(), String> {
+    trash::delete(path).map_err(|e| e.to_string())
+}
+
+/// Put the most recently trashed item that came from `original` back
+fn restore_from_trash(original: &Path) -> Result<(), String> {
+    let items = trash::os_limited::list().map_err(|e| e.to_string())?;
+    let item = items.into_iter()
+        .filter(|item| item.original_path() == original)
+        .max_by_key(|item| item.time_deleted)
+        .ok_or_else(|| "Not found in the trash".to_string())?;
+    trash::os_limited::restore_all([item]).map_err(|e| e.to_string())
+}
+
+fn remove(path: &Path) -> io::Result<()> {
+    if path.is_dir() { fs::remove_dir_all(path) } else { fs::remove_file(path) }
+}
+
+fn already_exists(path: &Path) -> io::Error {
+    io::Error::new(io::ErrorKind::AlreadyExists, format!("{} already exists", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Fresh empty folder for one test
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("curation-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn undo_a_move() {
+        let dir = scratch("move");
+        let from = dir.join("Vol 1.cbz");
+        let to = dir.join("Done").join("Vol 1.cbz");
+        fs::write(&from, b"pages").unwrap();
+        fs::create_dir(dir.join("Done")).unwrap();
+
+        move_book(&from, &to).unwrap();
+        assert!(!from.exists() && to.exists());
+        Curation::Moved { from: from.clone(), to: to.clone() }.undo().unwrap();
+        assert_eq!(fs::read(&from).unwrap(), b"pages");
+        assert!(!to.exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn undo_a_folder_copy() {
+        let dir = scratch("copy");
+        let original = dir.join("Vol 2");
+        let copy = dir.join("Vol 2 copy");
+        fs::create_dir(&original).unwrap();
+        fs::write(original.join("001.png"), b"page").unwrap();
+
+        copy_book(&original, &copy).unwrap();
+        assert_eq!(fs::read(copy.join("001.png")).unwrap(), b"page");
+        Curation::Copied { original: original.clone(), copy: copy.clone() }.undo().unwrap();
+        assert!(!copy.exists());
+        assert!(original.join("001.png").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn never_overwrite() {
+        let dir = scratch("overwrite");
+        let source = dir.join("a.cbz");
+        let dest = dir.join("b.cbz");
+        fs::write(&source, b"a").unwrap();
+        fs::write(&dest, b"b").unwrap();
+
+        assert_eq!(move_book(&source, &dest).unwrap_err().kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(copy_book(&source, &dest).unwrap_err().kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(fs::read(&dest).unwrap(), b"b");
+        assert!(source.exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
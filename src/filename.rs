@@ -0,0 +1,283 @@
+use std::cmp::Ordering;
+use std::ffi::OsStr;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use crate::source::IMAGE_EXTENSIONS;
+use crate::utils::windows_natural_cmp;
+
+const BOOK_EXTENSIONS: [&str; 5] = ["zip", "cbz", "rar", "cbr", "pdf"];
+
+/// Words that start a volume or chapter number, longest first so "vol" wins over "v".
+/// The number follows right after, with dots or spaces in between.
+const VOLUME_WORDS: [&str; 3] = ["volume", "vol", "v"];
+const CHAPTER_WORDS: [&str; 7] = ["chapter", "chap", "ch", "episode", "ep", "c", "#"];
+/// Marks of side stories, matched as whole words (ASCII case ignored)
+const EXTRA_WORDS: [&str; 9] = ["extra", "special", "omake", "side story", "bonus", "oneshot", "番外", "特別編", "おまけ"];
+
+/// What a file or folder name tells about the book. Numbers keep their decimals, "10.5" is the
+/// side story between chapters 10 and 11.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ParsedName {
+    pub series: String,
+    pub volume: Option<f64>,
+    pub chapter: Option<f64>,
+    /// The word marking a side story, as written in the name
+    pub extra: Option<String>,
+    /// Leading [Group] tag of scanlations
+    pub group: Option<String>,
+}
+
+impl ParsedName {
+    /// Parse a file or folder name. Archive and image extensions are dropped, other dots are
+    /// part of the name ("Vol.2").
+    pub fn parse(name: &str) -> Self {
+        let mut stem = name.trim();
+        if let Some((base, ext)) = stem.rsplit_once('.') {
+            let ext = ext.to_ascii_lowercase();
+            if BOOK_EXTENSIONS.contains(&ext.as_str()) || IMAGE_EXTENSIONS.contains(&ext.as_str()) {
+                stem = base;
+            }
+        }
+
+        let mut parsed = ParsedName::default();
+        // Leading tags: the first bracketed one names the group, the rest ("(C99)") are dropped
+        let mut rest = stem.trim();
+        while let Some(close) = match rest.chars().next() {
+            Some('[') => rest.find(']'),
+            Some('(') => rest.find(')'),
+            _ => None,
+        } {
+            if parsed.group.is_none() && rest.starts_with('[') {
+                parsed.group = Some(rest[1..close].trim().to_string());
+            }
+            rest = rest[close + 1..].trim_start();
+        }
+        // Trailing tags like "(2019)" or "[Digital]"
+        while let Some(open) = match rest.chars().last() {
+            Some(']') => rest.rfind('['),
+            Some(')') => rest.rfind('('),
+            _ => None,
+        } {
+            rest = rest[..open].trim_end();
+        }
+
+        // Byte positions match between `rest` and `lower`, only ASCII letters change
+        let lower = rest.to_ascii_lowercase();
+        let mut series_end = rest.len();
+
+        for word in EXTRA_WORDS {
+            if let Some(start) = find_word(&lower, word) {
+                parsed.extra = Some(rest[start..start + word.len()].to_string());
+                series_end = series_end.min(start);
+                break;
+            }
+        }
+
+        // 第3巻, 第12話
+        if let Some(start) = lower.find('第') {
+            let digits = start + '第'.len_utf8();
+            if let Some((number, end)) = number_at(&lower, digits) {
+                let unit = lower[end..].chars().next();
+                if unit == Some('巻') {
+                    parsed.volume = Some(number);
+                    series_end = series_end.min(start);
+                } else if matches!(unit, Some('話' | '章')) {
+                    parsed.chapter = Some(number);
+                    series_end = series_end.min(start);
+                }
+            }
+        }
+
+        if parsed.volume.is_none() {
+            if let Some((start, number)) = find_numbered(&lower, &VOLUME_WORDS) {
+                parsed.volume = Some(number);
+                series_end = series_end.min(start);
+            }
+        }
+        if parsed.chapter.is_none() {
+            if let Some((start, number)) = find_numbered(&lower, &CHAPTER_WORDS) {
+                parsed.chapter = Some(number);
+                series_end = series_end.min(start);
+            }
+        }
+
+        // "Title - 012" numbers the chapter, a bare "Title 03" the volume
+        if parsed.chapter.is_none() {
+            if let Some(start) = lower.rfind(" - ") {
+                if let Some((number, end)) = number_at(&lower, start + 3) {
+                    if lower[end..].trim().is_empty() {
+                        parsed.chapter = Some(number);
+                        series_end = series_end.min(start);
+                    }
+                }
+            }
+        }
+        if parsed.volume.is_none() && parsed.chapter.is_none() {
+            let head = rest[..series_end].trim_end();
+            let start = head.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.').len();
+            if start < head.len() && (start == 0 || !head[..start].ends_with(|c: char| c.is_alphabetic())) {
+                if let Some((number, _)) = number_at(&lower, start) {
+                    parsed.volume = Some(number);
+                    series_end = start;
+                }
+            }
+        }
+
+        parsed.series = rest[..series_end].trim_matches(|c: char| c.is_whitespace() || "-_.,:~".contains(c)).to_string();
+        parsed
+    }
+
+    pub fn of_path(path: &Path) -> Self {
+        Self::parse(&path.file_name().unwrap_or_default().to_string_lossy())
+    }
+
+    /// Name to show for a book: the parsed form when it has numbers, the file name otherwise
+    pub fn display_name(path: &Path) -> String {
+        let parsed = Self::of_path(path);
+        if parsed.is_numbered() {
+            parsed.display()
+        } else {
+            path.file_name().unwrap_or_default().to_string_lossy().to_string()
+        }
+    }
+
+    /// True when a volume or chapter was found, the name is worth showing in its parsed form
+    pub fn is_numbered(&self) -> bool {
+        self.volume.is_some() || self.chapter.is_some()
+    }
+
+    /// "Series  Vol. 2  Ch. 10.5  (Extra)"
+    pub fn display(&self) -> String {
+        let mut parts = Vec::new();
+        if !self.series.is_empty() {
+            parts.push(self.series.clone());
+        }
+        if let Some(volume) = self.volume {
+            parts.push(format!("Vol. {}", volume));
+        }
+        if let Some(chapter) = self.chapter {
+            parts.push(format!("Ch. {}", chapter));
+        }
+        if let Some(extra) = &self.extra {
+            parts.push(format!("({})", extra));
+        }
+        parts.join("  ")
+    }
+
+    /// Reading order: series by natural name, then volume and chapter. Side stories without a
+    /// number of their own come after the numbered books of their series.
+    pub fn reading_cmp(&self, other: &Self) -> Ordering {
+        let key = |name: &Self| (
+            name.extra.is_some() && !name.is_numbered(),
+            name.volume.unwrap_or(-1.0),
+            name.chapter.unwrap_or(-1.0),
+            name.extra.is_some(),
+        );
+        let (a, b) = (key(self), key(other));
+        windows_natural_cmp(OsStr::new(&self.series), OsStr::new(&other.series))
+            .then(a.0.cmp(&b.0))
+            .then(a.1.total_cmp(&b.1))
+            .then(a.2.total_cmp(&b.2))
+            .then(a.3.cmp(&b.3))
+    }
+}
+
+/// First `word` not inside a longer word
+fn find_word(lower: &str, word: &str) -> Option<usize> {
+    lower.match_indices(word).map(|(start, _)| start).find(|&start| {
+        !lower[..start].ends_with(|c: char| c.is_ascii_alphabetic())
+            && !lower[start + word.len()..].starts_with(|c: char| c.is_ascii_alphabetic())
+    })
+}
+
+/// First `word` followed by a number, not inside a longer word. Returns where the word starts.
+fn find_numbered(lower: &str, words: &[&str]) -> Option<(usize, f64)> {
+    for (start, _) in lower.char_indices() {
+        if lower[..start].ends_with(|c: char| c.is_ascii_alphabetic()) {
+            continue;
+        }
+        for word in words {
+            if !lower[start..].starts_with(word) {
+                continue;
+            }
+            let after = &lower[start + word.len()..];
+            let digits = start + word.len() + (after.len() - after.trim_start_matches(['.', ' ', '_']).len());
+            if let Some((number, _)) = number_at(lower, digits) {
+                return Some((start, number));
+            }
+        }
+    }
+    None
+}
+
+/// Number starting at byte `start`, "012" or "10.5", and the byte after it
+fn number_at(text: &str, start: usize) -> Option<(f64, usize)> {
+    let tail = text.get(start..)?;
+    let int_len = tail.find(|c: char| !c.is_ascii_digit()).unwrap_or(tail.len());
+    if int_len == 0 {
+        return None;
+    }
+    let mut len = int_len;
+    if let Some(fraction) = tail[int_len..].strip_prefix('.') {
+        let fraction_len = fraction.find(|c: char| !c.is_ascii_digit()).unwrap_or(fraction.len());
+        if fraction_len > 0 {
+            len += 1 + fraction_len;
+        }
+    }
+    tail[..len].parse().ok().map(|number| (number, start + len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn volume_after_v() {
+        let name = ParsedName::parse("Title v01.cbz");
+        assert_eq!(name.series, "Title");
+        assert_eq!(name.volume, Some(1.0));
+        assert_eq!(name.chapter, None);
+    }
+
+    #[test]
+    fn volume_after_vol_and_dot() {
+        let name = ParsedName::parse("Title Vol.2.zip");
+        assert_eq!(name.series, "Title");
+        assert_eq!(name.volume, Some(2.0));
+        assert_eq!(name.chapter, None);
+    }
+
+    #[test]
+    fn japanese_volume() {
+        let name = ParsedName::parse("タイトル 第3巻.zip");
+        assert_eq!(name.series, "タイトル");
+        assert_eq!(name.volume, Some(3.0));
+        assert_eq!(name.chapter, None);
+    }
+
+    #[test]
+    fn chapter_with_decimals() {
+        let name = ParsedName::parse("Title Chapter 10.5");
+        assert_eq!(name.series, "Title");
+        assert_eq!(name.volume, None);
+        assert_eq!(name.chapter, Some(10.5));
+    }
+
+    #[test]
+    fn group_and_dash_chapter() {
+        let name = ParsedName::parse("[Group] Title - 012.cbz");
+        assert_eq!(name.series, "Title");
+        assert_eq!(name.group.as_deref(), Some("Group"));
+        assert_eq!(name.volume, None);
+        assert_eq!(name.chapter, Some(12.0));
+    }
+
+    #[test]
+    fn side_story_after_its_chapter() {
+        let chapter = ParsedName::parse("Title Chapter 10");
+        let side_story = ParsedName::parse("Title Chapter 10.5");
+        let next = ParsedName::parse("Title Chapter 11");
+        assert_eq!(chapter.reading_cmp(&side_story), Ordering::Less);
+        assert_eq!(side_story.reading_cmp(&next), Ordering::Less);
+    }
+}
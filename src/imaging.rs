@@ -0,0 +1,362 @@
+use image::{DynamicImage, GenericImageView, RgbaImage};
+use crate::config::{CropMargins, ImageAdjustments, NightColorPages};
+use crate::pages::HalfSide;
+
+/// Read the image size from the start of a file, without decoding the pixels
+pub fn image_dimensions(bytes: &[u8]) -> Option<(u32, u32)> {
+    image::ImageReader::new(std::io::Cursor::new(bytes))
+        .with_guessed_format()
+        .ok()?
+        .into_dimensions()
+        .ok()
+}
+
+/// Landscape images are treated as two facing pages scanned together
+pub fn is_spread(width: u32, height: u32) -> bool {
+    width > height
+}
+
+/// Find the x position of the gutter between the two pages of a spread.
+/// Looks for the most uniform column around the middle, and falls back to the
+/// exact middle when the artwork runs across the gutter.
+pub fn find_gutter(img: &DynamicImage) -> u32 {
+    let (width, _) = img.dimensions();
+    let center = width / 2;
+
+    // Gutter detection doesn't need detail, work on a small grayscale copy
+    let small = img.thumbnail(400, 400).to_luma8();
+    let (sw, sh) = small.dimensions();
+    if sw < 10 || sh == 0 {
+        return center;
+    }
+
+    // Only search the middle 20% of the spread
+    let band_start = sw * 2 / 5;
+    let band_end = sw * 3 / 5;
+    let mid = sw as f32 / 2.0;
+
+    let mut best: Option<(f32, u32)> = None;
+    for x in band_start..=band_end {
+        let column: Vec<f32> = (0..sh).map(|y| small.get_pixel(x, y)[0] as f32).collect();
+        let mean = column.iter().sum::<f32>() / sh as f32;
+        let variance = column.iter().map(|v| (v - mean).powi(2)).sum::<f32>() / sh as f32;
+        // Prefer the column closest to the middle when several are equally uniform
+        let score = variance.sqrt() + (x as f32 - mid).abs() * 0.2;
+        if best.is_none_or(|(s, _)| score < s) {
+            best = Some((score, x));
+        }
+    }
+
+    match best {
+        Some((score, x)) if score < 20.0 => x * width / sw,
+        _ => center,
+    }
+}
+
+/// Cut one page out of a double page scan
+pub fn split_half(img: &DynamicImage, side: HalfSide) -> DynamicImage {
+    let (width, height) = img.dimensions();
+    let gutter = find_gutter(img).clamp(1, width.saturating_sub(1).max(1));
+    match side {
+        HalfSide::Left => img.crop_imm(0, 0, gutter, height),
+        HalfSide::Right => img.crop_imm(gutter, 0, width - gutter, height),
+    }
+}
+
+/// Detect uniform borders around a page. Pixels within `tolerance` of the border
+/// colour count as border, and a line may have a few noisy pixels (dust, scan noise).
+pub fn detect_margins(img: &DynamicImage, tolerance: u8) -> CropMargins {
+    let small = img.thumbnail(600, 600).to_luma8();
+    let (w, h) = small.dimensions();
+    if w < 8 || h < 8 {
+        return CropMargins::default();
+    }
+
+    // Border colour taken from the corners, white and black borders are both common
+    let mut corners = [(0, 0), (w - 1, 0), (0, h - 1), (w - 1, h - 1)].map(|(x, y)| small.get_pixel(x, y)[0]);
+    corners.sort_unstable();
+    let border = ((corners[1] as u16 + corners[2] as u16) / 2) as u8;
+
+    let is_margin_line = |pixels: &mut dyn Iterator<Item = u8>, len: u32| {
+        let noisy = pixels.filter(|v| v.abs_diff(border) > tolerance).count();
+        // Allow 1% of noisy pixels on a margin line
+        noisy * 100 <= len as usize
+    };
+    let row = |y: u32| is_margin_line(&mut (0..w).map(|x| small.get_pixel(x, y)[0]), w);
+    let col = |x: u32| is_margin_line(&mut (0..h).map(|y| small.get_pixel(x, y)[0]), h);
+
+    // Never cut more than a quarter of the page from one side
+    let max_x = w / 4;
+    let max_y = h / 4;
+    let top = (0..max_y).take_while(|&y| row(y)).count();
+    let bottom = (0..max_y).take_while(|&y| row(h - 1 - y)).count();
+    let left = (0..max_x).take_while(|&x| col(x)).count();
+    let right = (0..max_x).take_while(|&x| col(w - 1 - x)).count();
+
+    CropMargins {
+        left: left as f32 / w as f32,
+        top: top as f32 / h as f32,
+        right: right as f32 / w as f32,
+        bottom: bottom as f32 / h as f32,
+    }
+}
+
+/// Cut the given margins from a page
+pub fn crop_margins(img: &DynamicImage, margins: CropMargins) -> DynamicImage {
+    let (w, h) = img.dimensions();
+    let margins = margins.clamped();
+    let px = |fraction: f32, size: u32| (fraction * size as f32) as u32;
+    let left = px(margins.left, w);
+    let top = px(margins.top, h);
+    let width = w.saturating_sub(left + px(margins.right, w)).max(1);
+    let height = h.saturating_sub(top + px(margins.bottom, h)).max(1);
+    img.crop_imm(left, top, width, height)
+}
+
+/// Run the adjustment filter chain on a (resized) page
+pub fn apply_adjustments(img: DynamicImage, adjustments: &ImageAdjustments) -> DynamicImage {
+    if adjustments.is_neutral() {
+        return img;
+    }
+
+    let mut rgba = img.to_rgba8();
+    if adjustments.remove_tint {
+        remove_tint(&mut rgba);
+    }
+    if adjustments.auto_levels {
+        auto_levels(&mut rgba);
+    }
+    if adjustments.brightness != 0.0 || adjustments.contrast != 0.0 || adjustments.gamma != 1.0 {
+        let lut = tone_curve(adjustments.brightness, adjustments.contrast, adjustments.gamma);
+        for p in rgba.pixels_mut() {
+            for c in 0..3 {
+                p[c] = lut[p[c] as usize];
+            }
+        }
+    }
+    if adjustments.grayscale {
+        for p in rgba.pixels_mut() {
+            let l = luma(p[0], p[1], p[2]);
+            p[0] = l;
+            p[1] = l;
+            p[2] = l;
+        }
+    }
+    if adjustments.sharpen > 0.0 {
+        rgba = unsharp_mask(&rgba, adjustments.sharpen);
+    }
+    DynamicImage::ImageRgba8(rgba)
+}
+
+fn luma(r: u8, g: u8, b: u8) -> u8 {
+    ((r as u32 * 299 + g as u32 * 587 + b as u32 * 114) / 1000) as u8
+}
+
+/// Value at the given fraction of a 256 bins histogram
+fn percentile(histogram: &[u32; 256], total: u32, fraction: f32) -> u8 {
+    let target = (total as f32 * fraction) as u32;
+    let mut count = 0;
+    for (value, n) in histogram.iter().enumerate() {
+        count += n;
+        if count > target {
+            return value as u8;
+        }
+    }
+    255
+}
+
+/// Lookup table stretching `low..=high` to the full range
+fn levels_lut(low: u8, high: u8) -> [u8; 256] {
+    let mut lut = [0u8; 256];
+    let range = (high as f32 - low as f32).max(1.0);
+    for (v, out) in lut.iter_mut().enumerate() {
+        *out = ((v as f32 - low as f32) * 255.0 / range).round().clamp(0.0, 255.0) as u8;
+    }
+    lut
+}
+
+/// Stretch the brightness so the darkest ink is black and the paper is white.
+/// Works on the luminance so colours keep their balance.
+fn auto_levels(img: &mut RgbaImage) {
+    let mut histogram = [0u32; 256];
+    for p in img.pixels() {
+        histogram[luma(p[0], p[1], p[2]) as usize] += 1;
+    }
+    let total = img.width() * img.height();
+    let low = percentile(&histogram, total, 0.005);
+    let high = percentile(&histogram, total, 0.995);
+    // Nearly flat page (blank or solid colour), nothing to stretch
+    if high <= low.saturating_add(10) {
+        return;
+    }
+
+    let lut = levels_lut(low, high);
+    for p in img.pixels_mut() {
+        for c in 0..3 {
+            p[c] = lut[p[c] as usize];
+        }
+    }
+}
+
+/// Remove the yellow/blue cast of old scans by levelling every colour channel
+/// on its own, so the paper becomes neutral white and the ink neutral black.
+fn remove_tint(img: &mut RgbaImage) {
+    let mut histograms = [[0u32; 256]; 3];
+    for p in img.pixels() {
+        for c in 0..3 {
+            histograms[c][p[c] as usize] += 1;
+        }
+    }
+    let total = img.width() * img.height();
+    let luts = histograms.map(|histogram| {
+        let low = percentile(&histogram, total, 0.01);
+        let high = percentile(&histogram, total, 0.99);
+        if high <= low.saturating_add(10) {
+            levels_lut(0, 255)
+        } else {
+            levels_lut(low, high)
+        }
+    });
+
+    for p in img.pixels_mut() {
+        for c in 0..3 {
+            p[c] = luts[c][p[c] as usize];
+        }
+    }
+}
+
+/// Brightness, contrast and gamma combined into a single lookup table
+fn tone_curve(brightness: f32, contrast: f32, gamma: f32) -> [u8; 256] {
+    let mut lut = [0u8; 256];
+    let gamma = gamma.max(0.05);
+    for (v, out) in lut.iter_mut().enumerate() {
+        let mut x = v as f32 / 255.0;
+        x = (x - 0.5) * (1.0 + contrast) + 0.5 + brightness;
+        x = x.clamp(0.0, 1.0).powf(1.0 / gamma);
+        *out = (x * 255.0).round() as u8;
+    }
+    lut
+}
+
+/// Classic unsharp mask: add back the difference between the page and a blurred copy
+fn unsharp_mask(img: &RgbaImage, amount: f32) -> RgbaImage {
+    let blurred = image::imageops::fast_blur(img, 1.2);
+    let mut out = img.clone();
+    for (p, b) in out.pixels_mut().zip(blurred.pixels()) {
+        for c in 0..3 {
+            let v = p[c] as f32 + (p[c] as f32 - b[c] as f32) * amount;
+            p[c] = v.round().clamp(0.0, 255.0) as u8;
+        }
+    }
+    out
+}
+
+/// Black and white pages, allowing a few coloured pixels from scan noise
+pub fn is_monochrome(img: &DynamicImage) -> bool {
+    let small = img.thumbnail(200, 200).to_rgb8();
+    let total = small.pixels().len();
+    let colored = small.pixels()
+        .filter(|p| {
+            let max = p[0].max(p[1]).max(p[2]);
+            let min = p[0].min(p[1]).min(p[2]);
+            max - min > 40
+        })
+        .count();
+    colored * 100 < total * 2
+}
+
+/// Night reading: black and white pages are inverted to light text on a dark page,
+/// colour pages are kept or given a warm tint.
+pub fn apply_night_mode(img: DynamicImage, color_pages: NightColorPages, warmth: f32) -> DynamicImage {
+    if is_monochrome(&img) {
+        let mut rgba = img.to_rgba8();
+        for p in rgba.pixels_mut() {
+            // Dim the inverted ink to a light gray instead of pure white, easier on the eyes
+            let v = ((255 - luma(p[0], p[1], p[2])) as f32 * 0.8) as u8;
+            p[0] = v;
+            p[1] = v;
+            p[2] = v;
+        }
+        return DynamicImage::ImageRgba8(rgba);
+    }
+
+    match color_pages {
+        NightColorPages::Keep => img,
+        NightColorPages::WarmTint => {
+            let warmth = warmth.clamp(0.0, 1.0);
+            let mut rgba = img.to_rgba8();
+            for p in rgba.pixels_mut() {
+                p[0] = (p[0] as f32 * (1.0 - 0.1 * warmth)) as u8;
+                p[1] = (p[1] as f32 * (1.0 - 0.3 * warmth)) as u8;
+                p[2] = (p[2] as f32 * (1.0 - 0.6 * warmth)) as u8;
+            }
+            DynamicImage::ImageRgba8(rgba)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Luma;
+
+    /// Striped artwork that no column of is uniform, with white columns in `gutter`
+    fn spread(gutter: std::ops::Range<u32>) -> DynamicImage {
+        DynamicImage::ImageLuma8(image::GrayImage::from_fn(400, 200, |x, y| {
+            if gutter.contains(&x) || (x / 3 + y / 5) % 2 == 0 { Luma([255]) } else { Luma([0]) }
+        }))
+    }
+
+    #[test]
+    fn gutter_off_center() {
+        let gutter = find_gutter(&spread(216..222));
+        assert!((216..222).contains(&gutter), "gutter at {gutter}");
+    }
+
+    #[test]
+    fn no_gutter_splits_in_the_middle() {
+        assert_eq!(find_gutter(&spread(0..0)), 200);
+    }
+
+    #[test]
+    fn halves_meet_at_the_gutter() {
+        let img = spread(216..222);
+        let left = split_half(&img, HalfSide::Left).width();
+        let right = split_half(&img, HalfSide::Right).width();
+        assert!((216..222).contains(&left));
+        assert_eq!(left + right, 400);
+    }
+
+    /// White page with black artwork covering `x` by `y`
+    fn page(x: std::ops::Range<u32>, y: std::ops::Range<u32>) -> DynamicImage {
+        DynamicImage::ImageLuma8(image::GrayImage::from_fn(600, 600, |px, py| {
+            if x.contains(&px) && y.contains(&py) { Luma([0]) } else { Luma([255]) }
+        }))
+    }
+
+    #[test]
+    fn margins_around_the_artwork() {
+        let margins = detect_margins(&page(60..540, 90..500), 16);
+        assert_eq!(margins.left, 0.1);
+        assert_eq!(margins.right, 0.1);
+        assert_eq!(margins.top, 0.15);
+        assert_eq!(margins.bottom, 100.0 / 600.0);
+    }
+
+    #[test]
+    fn margins_tolerate_dust() {
+        let DynamicImage::ImageLuma8(mut img) = page(60..540, 90..510) else { unreachable!() };
+        img.put_pixel(300, 20, Luma([0]));
+        img.put_pixel(20, 300, Luma([0]));
+        let margins = detect_margins(&DynamicImage::ImageLuma8(img), 16);
+        assert_eq!(margins.top, 0.15);
+        assert_eq!(margins.left, 0.1);
+    }
+
+    #[test]
+    fn margins_stop_at_a_quarter() {
+        let margins = detect_margins(&page(295..305, 295..305), 16);
+        assert_eq!(margins.left, 0.25);
+        assert_eq!(margins.bottom, 0.25);
+    }
+}
@@ -0,0 +1,256 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use eframe::egui;
+use crate::book_db::BookId;
+use crate::filename::ParsedName;
+use crate::config::{FileSorting, LibrarySort, SortOrder, SourceMode};
+use crate::ordering::sort_paths;
+use crate::source::{self, PageReader, IMAGE_EXTENSIONS};
+use crate::thumbnails::{load_thumbnail, modified_time, thumbnail_dir};
+use crate::utils::windows_natural_cmp;
+
+/// One readable source found under the library roots
+pub struct LibraryBook {
+    pub path: PathBuf,
+    pub title: String,
+    pub name: ParsedName,
+    pub mode: SourceMode,
+    pub page_count: usize,
+    /// Seconds since the epoch
+    pub modified: u64,
+    pub id: BookId,
+    first_page: String,
+}
+
+struct CoverRequest {
+    index: usize,
+    path: PathBuf,
+    mode: SourceMode,
+    first_page: String,
+}
+
+/// (scan generation, book index, cover)
+type CoverResult = (u64, usize, Option<egui::ColorImage>);
+
+/// What a sorted view was made for: (sort, descending, filter, number of books)
+type ViewKey = (LibrarySort, bool, String, usize);
+
+/// The books under the configured root folders and their covers. Scanning and
+/// cover extraction run on worker threads; `poll` picks up their results.
+pub struct Library {
+    pub books: Vec<LibraryBook>,
+    pub scanning: bool,
+    scanned: bool,
+    generation: u64,
+    scan_results: Option<Receiver<Option<LibraryBook>>>,
+    cover_requests: Option<Sender<CoverRequest>>,
+    cover_results_tx: Sender<CoverResult>,
+    cover_results: Receiver<CoverResult>,
+    covers: HashMap<usize, egui::TextureHandle>,
+    requested: HashSet<usize>,
+    view: Option<(ViewKey, Vec<usize>)>,
+}
+
+impl Library {
+    pub fn new() -> Self {
+        let (cover_results_tx, cover_results) = channel();
+        Self {
+            books: Vec::new(),
+            scanning: false,
+            scanned: false,
+            generation: 0,
+            scan_results: None,
+            cover_requests: None,
+            cover_results_tx,
+            cover_results,
+            covers: HashMap::new(),
+            requested: HashSet::new(),
+            view: None,
+        }
+    }
+
+    pub fn is_scanned(&self) -> bool {
+        self.scanned
+    }
+
+    /// Forget the books found so far and walk the roots again
+    pub fn scan(&mut self, roots: &[PathBuf], sorting: &FileSorting, byte_fix: bool, ctx: &egui::Context) {
+        self.generation += 1;
+        self.books.clear();
+        self.covers.clear();
+        self.requested.clear();
+        self.view = None;
+        self.scanning = true;
+        self.scanned = true;
+
+        // Dropping the old receiver and sender stops the old workers
+        let (tx, rx) = channel();
+        self.scan_results = Some(rx);
+        let roots = roots.to_vec();
+        let sorting = sorting.clone();
+        let scan_ctx = ctx.clone();
+        std::thread::spawn(move || {
+            for root in &roots {
+                let mut visit = |path: &Path, mode| send_book(path, mode, sorting.order_for(path), &tx, &scan_ctx);
+                if !walk_sources(root, &sorting, &mut visit) {
+                    return;
+                }
+            }
+            let _ = tx.send(None);
+            scan_ctx.request_repaint();
+        });
+
+        let (tx, rx) = channel::<CoverRequest>();
+        self.cover_requests = Some(tx);
+        let results = self.cover_results_tx.clone();
+        let generation = self.generation;
+        let ctx = ctx.clone();
+        std::thread::spawn(move || {
+            let cache_dir = thumbnail_dir();
+            let mut stack: Vec<CoverRequest> = Vec::new();
+            loop {
+                if stack.is_empty() {
+                    match rx.recv() {
+                        Ok(request) => stack.push(request),
+                        Err(_) => return,
+                    }
+                }
+                stack.extend(rx.try_iter());
+                // The newest request is on screen
+                let Some(request) = stack.pop() else { continue };
+
+                let mut reader = PageReader::open(request.mode, &request.path, byte_fix);
+                let source_mtime = modified_time(&request.path);
+                let cover = load_thumbnail(&mut reader, &cache_dir, &request.path, source_mtime, &request.first_page, 0);
+                if results.send((generation, request.index, cover)).is_err() {
+                    return;
+                }
+                ctx.request_repaint();
+            }
+        });
+    }
+
+    /// Take the books and covers the workers found since the last frame
+    pub fn poll(&mut self, ctx: &egui::Context) {
+        if let Some(results) = &self.scan_results {
+            for result in results.try_iter() {
+                match result {
+                    Some(book) => self.books.push(book),
+                    None => self.scanning = false,
+                }
+            }
+        }
+        for (generation, index, cover) in self.cover_results.try_iter() {
+            if generation != self.generation {
+                continue;
+            }
+            if let Some(cover) = cover {
+                let texture = ctx.load_texture(format!("cover#{}", index), cover, egui::TextureOptions::LINEAR);
+                self.covers.insert(index, texture);
+            }
+        }
+    }
+
+    /// Cover of a book, asking the worker for it when missing
+    pub fn cover(&mut self, index: usize) -> Option<&egui::TextureHandle> {
+        if self.requested.insert(index) {
+            if let (Some(requests), Some(book)) = (&self.cover_requests, self.books.get(index)) {
+                let _ = requests.send(CoverRequest {
+                    index,
+                    path: book.path.clone(),
+                    mode: book.mode,
+                    first_page: book.first_page.clone(),
+                });
+            }
+        }
+        self.covers.get(&index)
+    }
+
+    /// Indices of the books whose title or path contain every word of `filter`, in the chosen order.
+    /// Kept until the sort, the filter or the books change.
+    pub fn sorted_view(&mut self, sort: LibrarySort, descending: bool, filter: &str) -> Vec<usize> {
+        let key = (sort, descending, filter.to_string(), self.books.len());
+        if let Some((cached_key, view)) = &self.view {
+            if *cached_key == key {
+                return view.clone();
+            }
+        }
+        let words: Vec<String> = filter.split_whitespace().map(|w| w.to_lowercase()).collect();
+        let mut view: Vec<usize> = (0..self.books.len())
+            .filter(|&i| {
+                let haystack = self.books[i].path.to_string_lossy().to_lowercase();
+                words.iter().all(|w| haystack.contains(w.as_str()))
+            })
+            .collect();
+
+        view.sort_by(|&a, &b| {
+            let (a, b) = (&self.books[a], &self.books[b]);
+            let order = match sort {
+                LibrarySort::Title => a.name.reading_cmp(&b.name),
+                LibrarySort::Modified => a.modified.cmp(&b.modified),
+                LibrarySort::PageCount => a.page_count.cmp(&b.page_count),
+            };
+            // Same keys keep the folder order
+            order.then_with(|| windows_natural_cmp(a.path.as_os_str(), b.path.as_os_str()))
+        });
+        if descending {
+            view.reverse();
+        }
+        self.view = Some((key, view.clone()));
+        view
+    }
+}
+
+/// Visit every source below `dir`, depth first in the order set for each folder. A folder with
+/// images in it is a source itself, before its subfolders. Returns false once `visit` asked to stop.
+pub fn walk_sources(dir: &Path, sorting: &FileSorting, visit: &mut dyn FnMut(&Path, SourceMode) -> bool) -> bool {
+    let Ok(entries) = fs::read_dir(dir) else { return true };
+    let mut paths: Vec<PathBuf> = entries.flatten()
+        .map(|entry| entry.path())
+        .filter(|p| !p.file_name().unwrap_or_default().to_string_lossy().starts_with('.'))
+        .collect();
+    sort_paths(&mut paths, sorting.order_for(dir));
+
+    let has_images = paths.iter().any(|p| {
+        p.is_file() && p.extension().map_or(false, |ext| IMAGE_EXTENSIONS.contains(&ext.to_string_lossy().to_lowercase().as_str()))
+    });
+    if has_images && !visit(dir, SourceMode::Folder) {
+        return false;
+    }
+
+    for path in paths {
+        if path.is_dir() {
+            if !walk_sources(&path, sorting, visit) {
+                return false;
+            }
+        } else {
+            let mode = source::source_mode_of(&path);
+            if mode != SourceMode::Folder && !visit(&path, mode) {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+/// Returns false once nobody listens anymore
+fn send_book(path: &Path, mode: SourceMode, page_order: SortOrder, tx: &Sender<Option<LibraryBook>>, ctx: &egui::Context) -> bool {
+    let pages = source::list_pages(mode, path, page_order);
+    let Some(first_page) = pages.first().cloned() else { return true };
+    let title = if mode == SourceMode::Folder { path.file_name() } else { path.file_stem() };
+    let book = LibraryBook {
+        path: path.to_path_buf(),
+        title: title.unwrap_or_default().to_string_lossy().to_string(),
+        name: ParsedName::of_path(path),
+        mode,
+        page_count: pages.len(),
+        modified: modified_time(path),
+        id: BookId::of(path),
+        first_page,
+    };
+    let sent = tx.send(Some(book)).is_ok();
+    ctx.request_repaint();
+    sent
+}
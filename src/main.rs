@@ -4,6 +4,22 @@ mod config;
 mod font;
 mod app;
 mod utils;
+mod pages;
+mod book_settings;
+mod imaging;
+mod panels;
+mod tiles;
+mod resample;
+mod source;
+mod thumbnails;
+mod library;
+mod book_db;
+mod session;
+mod ordering;
+mod filename;
+mod watcher;
+mod curation;
+mod page_loader;
 
 use app::MangaReader;
 
@@ -19,15 +35,27 @@ fn main() -> eframe::Result<()> {
     let args: Vec<String> = std::env::args().collect();
     let initial_path = args.get(1).map(std::path::PathBuf::from);
 
-    let native_options = eframe::NativeOptions {
-        viewport: egui::ViewportBuilder::default()
-            .with_maximized(true)
-            .with_decorations(true)
-            .with_icon(std::sync::Arc::new(egui::IconData {
+    // The window comes back where it was when the last session is restored
+    let session = session::Session::load();
+    let mut viewport = egui::ViewportBuilder::default()
+        .with_maximized(session.as_ref().map_or(true, |s| s.maximized))
+        .with_decorations(true)
+        .with_icon(std::sync::Arc::new(egui::IconData {
             rgba: icon.into_raw(),
             width,
             height,
-        })),
+        }));
+    if let Some(session) = &session {
+        if let Some(size) = session.window_size {
+            viewport = viewport.with_inner_size(size);
+        }
+        if let Some(pos) = session.window_pos {
+            viewport = viewport.with_position(pos);
+        }
+    }
+
+    let native_options = eframe::NativeOptions {
+        viewport,
         ..Default::default()
     };
 
@@ -35,7 +63,7 @@ fn main() -> eframe::Result<()> {
         "Rust Manga Reader for Windows - Productivity",
         native_options,
         Box::new(|cc| {
-            Ok(Box::new(MangaReader::new(cc, initial_path)))
+            Ok(Box::new(MangaReader::new(cc, initial_path, session)))
         }),
     )
 }
\ No newline at end of file
@@ -0,0 +1,126 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::SystemTime;
+use crate::config::{SortKey, SortOrder, SourceMode};
+use crate::filename::ParsedName;
+use crate::source::{source_mode_of, PageReader};
+use crate::utils::windows_natural_sort;
+
+/// Order the entries of one folder. Entries with the same key keep the natural name order,
+/// descending too.
+pub fn sort_paths(paths: &mut [PathBuf], order: SortOrder) {
+    windows_natural_sort(paths);
+    let descending = order.descending;
+    match order.key {
+        SortKey::ParsedName => sort_keyed(paths, descending, ParsedName::of_path, |a, b| a.reading_cmp(b)),
+        SortKey::NameNatural => {
+            // The key is the name itself, there are no ties
+            if descending {
+                paths.reverse();
+            }
+        }
+        SortKey::NameLexical => sort_keyed(paths, descending, |p| p.file_name().map(|name| name.to_os_string()), Ord::cmp),
+        SortKey::Modified => sort_keyed(paths, descending, |p| file_time(p, |meta| meta.modified()), Ord::cmp),
+        SortKey::Created => sort_keyed(paths, descending, |p| file_time(p, |meta| meta.created()), Ord::cmp),
+        SortKey::Size => sort_keyed(paths, descending, size_of, Ord::cmp),
+        SortKey::ComicInfo => {
+            // Books without a ComicInfo go after the numbered ones, in both directions
+            sort_keyed(paths, false, cached_comic_info_number, |a, b| match (a, b) {
+                (Some(a), Some(b)) => {
+                    let ordering = a.0.total_cmp(&b.0).then(a.1.total_cmp(&b.1));
+                    if descending { ordering.reverse() } else { ordering }
+                }
+                (a, b) => b.is_some().cmp(&a.is_some()),
+            });
+        }
+    }
+}
+
+/// Stable sort by a key made once per path, so equal keys keep the order they had
+fn sort_keyed<K>(paths: &mut [PathBuf], descending: bool, key: impl Fn(&Path) -> K, cmp: impl Fn(&K, &K) -> Ordering) {
+    let mut keyed: Vec<(K, PathBuf)> = paths.iter().map(|p| (key(p), p.clone())).collect();
+    keyed.sort_by(|(a, _), (b, _)| if descending { cmp(b, a) } else { cmp(a, b) });
+    for (slot, (_, path)) in paths.iter_mut().zip(keyed) {
+        *slot = path;
+    }
+}
+
+/// `sort_paths` for paths held as strings, like the pages of a folder source
+pub fn sort_path_strings(names: &mut [String], order: SortOrder) {
+    let mut paths: Vec<PathBuf> = names.iter().map(PathBuf::from).collect();
+    sort_paths(&mut paths, order);
+    for (name, path) in names.iter_mut().zip(paths) {
+        *name = path.to_string_lossy().to_string();
+    }
+}
+
+fn file_time(path: &Path, time: impl Fn(&fs::Metadata) -> std::io::Result<SystemTime>) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| time(&meta)).ok()
+}
+
+/// Size of a file, or of the files directly inside a folder
+fn size_of(path: &Path) -> u64 {
+    if !path.is_dir() {
+        return fs::metadata(path).map_or(0, |meta| meta.len());
+    }
+    fs::read_dir(path).map_or(0, |entries| {
+        entries.flatten()
+            .filter_map(|entry| entry.metadata().ok())
+            .filter(|meta| meta.is_file())
+            .map(|meta| meta.len())
+            .sum()
+    })
+}
+
+/// `comic_info_number` of the books sorted so far, with the time of the file it was read from
+type ComicInfoCache = HashMap<PathBuf, (Option<SystemTime>, Option<(f64, f64)>)>;
+
+fn comic_info_cache() -> &'static Mutex<ComicInfoCache> {
+    static CACHE: OnceLock<Mutex<ComicInfoCache>> = OnceLock::new();
+    CACHE.get_or_init(Default::default)
+}
+
+/// `comic_info_number`, only read again when the book changed. Each folder is sorted again
+/// whenever a book is opened, this keeps that from opening every archive next to it.
+fn cached_comic_info_number(path: &Path) -> Option<(f64, f64)> {
+    let info_file = if path.is_dir() { path.join("ComicInfo.xml") } else { path.to_path_buf() };
+    let modified = file_time(&info_file, |meta| meta.modified());
+    let cached = comic_info_cache().lock().ok()
+        .and_then(|cache| cache.get(path).filter(|(time, _)| *time == modified).map(|(_, number)| *number));
+    if let Some(number) = cached {
+        return number;
+    }
+    let number = comic_info_number(path);
+    if let Ok(mut cache) = comic_info_cache().lock() {
+        cache.insert(path.to_path_buf(), (modified, number));
+    }
+    number
+}
+
+/// (volume, number) from the ComicInfo.xml of an archive or folder. A missing field counts as -1.
+fn comic_info_number(path: &Path) -> Option<(f64, f64)> {
+    let mode = source_mode_of(path);
+    let xml = match mode {
+        SourceMode::Zip | SourceMode::Rar => PageReader::open(mode, path, false).read_bytes("ComicInfo.xml")?,
+        SourceMode::Folder => fs::read(path.join("ComicInfo.xml")).ok()?,
+        SourceMode::Pdf => return None,
+    };
+    let xml = String::from_utf8_lossy(&xml);
+    let volume = tag_number(&xml, "Volume");
+    let number = tag_number(&xml, "Number");
+    if volume.is_none() && number.is_none() {
+        return None;
+    }
+    Some((volume.unwrap_or(-1.0), number.unwrap_or(-1.0)))
+}
+
+/// Leading number of the text of `<tag>`, "10.5" or the 12 of "12b"
+fn tag_number(xml: &str, tag: &str) -> Option<f64> {
+    let start = xml.find(&format!("<{}>", tag))? + tag.len() + 2;
+    let text = xml[start..].split('<').next()?.trim();
+    let end = text.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(text.len());
+    text[..end].parse().ok()
+}
@@ -0,0 +1,341 @@
+use std::path::PathBuf;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::time::{Duration, Instant};
+use eframe::egui;
+use image::imageops::FilterType;
+use image::DynamicImage;
+use crate::config::{CropMargins, ImageAdjustments, NightColorPages, SourceMode};
+use crate::imaging;
+use crate::pages::HalfSide;
+use crate::panels::{self, PanelRect};
+use crate::resample;
+use crate::source::PageReader;
+
+/// Time spent in each stage of loading the pages on screen, for the debug overlay
+#[derive(Default, Clone, Copy)]
+pub struct LoadTimings {
+    pub decode: Duration,
+    pub preview: Duration,
+    pub resize: Duration,
+    pub filters: Duration,
+    pub upload: Duration,
+}
+
+/// One page of a pair that is not in the texture cache yet
+#[derive(Clone)]
+pub struct PageSpec {
+    pub file_index: usize,
+    pub filename: String,
+    /// Name of the page in the texture cache
+    pub cache_name: String,
+    /// The side of a split double page scan
+    pub half: Option<HalfSide>,
+    /// Find the panels of the page too, for panel view
+    pub detect_panels: bool,
+}
+
+/// The settings that go into making a page, copied out of the app so the pages can be
+/// made on a worker thread
+#[derive(Clone)]
+pub struct PageRecipe {
+    pub mode: SourceMode,
+    pub source: PathBuf,
+    pub byte_fix: bool,
+    /// Pdf pages are rendered this many pixels high
+    pub pdf_height: f32,
+    pub crop_override: Option<CropMargins>,
+    /// Tolerance of the automatic crop, `None` when it is off
+    pub auto_crop: Option<u8>,
+    /// Facing pages get the same top and bottom crop
+    pub shared_crop: bool,
+    /// `None` keeps the original size
+    pub filter: Option<FilterType>,
+    pub dither: bool,
+    pub screen_height: f32,
+    pub texture_zoom: f32,
+    pub max_texture_side: u32,
+    /// Keep the unfiltered page for the live adjustment preview
+    pub keep_base: bool,
+    pub adjustments: ImageAdjustments,
+    /// Color pages and warmth, when night mode is on
+    pub night_mode: Option<(NightColorPages, f32)>,
+    pub transparency_support: bool,
+    pub right_to_left: bool,
+}
+
+/// A page decoded at its original size, split and cropped
+pub struct DecodedPage {
+    pub cache_name: String,
+    pub image: DynamicImage,
+    /// Margins cut from the page, to place things saved in whole page coordinates
+    pub crop: CropMargins,
+}
+
+/// A page ready to be uploaded
+pub struct PreparedPage {
+    pub cache_name: String,
+    pub crop: CropMargins,
+    pub image: egui::ColorImage,
+    /// The full page when it is too large for one texture, `image` is a smaller stand-in then
+    pub full_image: Option<egui::ColorImage>,
+    /// The page before the adjustments, for the live preview
+    pub base: Option<DynamicImage>,
+    pub panels: Option<Vec<PanelRect>>,
+}
+
+/// Both pages of a pair, made from one recipe
+pub struct PreparedPair {
+    pub pages: [Option<PreparedPage>; 2],
+    /// Whether the pages shared their top and bottom crop
+    pub shared_crop: bool,
+    pub timings: LoadTimings,
+}
+
+/// Decode, split and crop the pages of a pair at their original size
+pub fn decode_pages(recipe: &PageRecipe, specs: &[Option<PageSpec>; 2]) -> [Option<DecodedPage>; 2] {
+    let mut images: [Option<DecodedPage>; 2] = [None, None];
+    if specs.iter().all(Option::is_none) {
+        return images;
+    }
+    let mut reader = PageReader::open(recipe.mode, &recipe.source, recipe.byte_fix);
+
+    // Both halves of a split scan come from the same image, only decode it once
+    let mut decoded: Option<(usize, DynamicImage)> = None;
+
+    for (slot, spec) in images.iter_mut().zip(specs) {
+        let Some(spec) = spec else { continue };
+        let img = match decoded.take() {
+            Some((index, img)) if index == spec.file_index => img,
+            _ => match reader.decode(&spec.filename, spec.file_index, recipe.pdf_height) {
+                Some(img) => img,
+                None => continue,
+            },
+        };
+
+        let page_img = if let Some(side) = spec.half {
+            let half = imaging::split_half(&img, side);
+            decoded = Some((spec.file_index, img));
+            half
+        } else {
+            img
+        };
+        *slot = Some(DecodedPage { cache_name: spec.cache_name.clone(), image: page_img, crop: CropMargins::default() });
+    }
+
+    crop_pair(recipe, &mut images);
+    images
+}
+
+/// Cut the page margins, either detected or set manually for the book.
+/// Facing pages share the same top and bottom crop so spreads still line up.
+fn crop_pair(recipe: &PageRecipe, images: &mut [Option<DecodedPage>; 2]) {
+    let margins: [Option<CropMargins>; 2] = if let Some(manual) = recipe.crop_override {
+        images.each_ref().map(|entry| entry.as_ref().map(|_| manual))
+    } else if let Some(tolerance) = recipe.auto_crop {
+        images.each_ref().map(|entry| entry.as_ref().map(|page| imaging::detect_margins(&page.image, tolerance)))
+    } else {
+        return;
+    };
+
+    let margins = match margins {
+        [Some(a), Some(b)] if recipe.shared_crop => {
+            let top = a.top.min(b.top);
+            let bottom = a.bottom.min(b.bottom);
+            [Some(CropMargins { top, bottom, ..a }), Some(CropMargins { top, bottom, ..b })]
+        }
+        _ => margins,
+    };
+
+    for (entry, margin) in images.iter_mut().zip(margins) {
+        if let (Some(page), Some(margin)) = (entry.as_mut(), margin) {
+            page.image = imaging::crop_margins(&page.image, margin);
+            page.crop = margin.clamped();
+        }
+    }
+}
+
+/// Resize a decoded page for the screen and apply the filters. Only the upload is left.
+pub fn prepare_page(recipe: &PageRecipe, page: DecodedPage, detect_panels: bool, timings: &mut LoadTimings) -> PreparedPage {
+    let DecodedPage { cache_name, image: img, crop } = page;
+    let panels = detect_panels.then(|| panels::detect_panels(&img, recipe.right_to_left));
+
+    let resize_start = Instant::now();
+    let processed_img = if let Some(filter_type) = recipe.filter {
+        let aspect_ratio = img.width() as f32 / img.height() as f32;
+        // Decode at the resolution the zoom needs, but don't enlarge past the original
+        // size when zoomed in, the GPU can magnify that for free
+        let mut target_h = recipe.screen_height * recipe.texture_zoom;
+        if recipe.texture_zoom > 1.0 {
+            target_h = target_h.min(recipe.screen_height.max(img.height() as f32));
+        }
+        let target_w = (target_h * aspect_ratio) as u32;
+        resample::resize(&img, target_w, target_h as u32, filter_type, recipe.dither)
+    } else {
+        img // No resizing needed, return original
+    };
+
+    let resize_time = resize_start.elapsed();
+    let adjust_start = Instant::now();
+
+    let max_side = recipe.max_texture_side;
+    let oversized = processed_img.width().max(processed_img.height()) > max_side;
+
+    let base = recipe.keep_base.then(|| {
+        if oversized {
+            processed_img.resize(max_side, max_side, FilterType::Triangle)
+        } else {
+            processed_img.clone()
+        }
+    });
+    let processed_img = apply_filters(processed_img, &recipe.adjustments, recipe.night_mode);
+
+    let adjust_time = adjust_start.elapsed();
+    let process_start = Instant::now();
+
+    // Too large for one texture: the page is drawn in tiles, and the single
+    // texture becomes a smaller stand-in used for the layout
+    let (processed_img, full_image) = if oversized {
+        let full_image = to_color_image(&processed_img, recipe.transparency_support);
+        (processed_img.resize(max_side, max_side, FilterType::Triangle), Some(full_image))
+    } else {
+        (processed_img, None)
+    };
+    let image = to_color_image(&processed_img, recipe.transparency_support);
+
+    let process_time = process_start.elapsed();
+
+    #[cfg(debug_assertions)]
+    {
+        println!("----------------------------------");
+        println!("resize_time: {:?}", resize_time);
+        println!("adjust_time: {:?}", adjust_time);
+        println!("process_time: {:?}", process_time);
+        println!("total: {:?}", process_time + resize_time + adjust_time);
+        println!("filter: {:?}", recipe.filter);
+        println!("----------------------------------");
+    }
+
+    timings.resize += resize_time;
+    timings.filters += adjust_time;
+    timings.upload += process_time;
+    PreparedPage { cache_name, crop, image, full_image, base, panels }
+}
+
+/// Everything but the upload of a pair
+pub fn prepare_pair(recipe: &PageRecipe, specs: &[Option<PageSpec>; 2]) -> PreparedPair {
+    let mut timings = LoadTimings::default();
+    let decode_start = Instant::now();
+    let images = decode_pages(recipe, specs);
+    timings.decode = decode_start.elapsed();
+
+    let shared_crop = recipe.shared_crop && images.iter().all(Option::is_some);
+    let detect = specs.each_ref().map(|spec| spec.as_ref().is_some_and(|spec| spec.detect_panels));
+    let mut pages: [Option<PreparedPage>; 2] = [None, None];
+    for (i, entry) in images.into_iter().enumerate() {
+        if let Some(page) = entry {
+            pages[i] = Some(prepare_page(recipe, page, detect[i], &mut timings));
+        }
+    }
+    PreparedPair { pages, shared_crop, timings }
+}
+
+/// The pages at their original size, up to the texture limit, with the same crop and
+/// filters as the screen pages. For the magnifier.
+pub fn prepare_full_pair(recipe: &PageRecipe, specs: &[Option<PageSpec>; 2]) -> PreparedPair {
+    let mut timings = LoadTimings::default();
+    let decode_start = Instant::now();
+    let images = decode_pages(recipe, specs);
+    timings.decode = decode_start.elapsed();
+
+    let shared_crop = recipe.shared_crop && images.iter().all(Option::is_some);
+    let max_side = recipe.max_texture_side;
+    let pages = images.map(|entry| entry.map(|DecodedPage { cache_name, image, crop }| {
+        let image = if image.width().max(image.height()) > max_side {
+            image.resize(max_side, max_side, FilterType::Triangle)
+        } else {
+            image
+        };
+        let image = apply_filters(image, &recipe.adjustments, recipe.night_mode);
+        PreparedPage {
+            cache_name,
+            crop,
+            image: to_color_image(&image, recipe.transparency_support),
+            full_image: None,
+            base: None,
+            panels: None,
+        }
+    }));
+    PreparedPair { pages, shared_crop, timings }
+}
+
+/// The adjustments, then night mode
+pub fn apply_filters(img: DynamicImage, adjustments: &ImageAdjustments, night_mode: Option<(NightColorPages, f32)>) -> DynamicImage {
+    let img = imaging::apply_adjustments(img, adjustments);
+    match night_mode {
+        Some((color_pages, warmth)) => imaging::apply_night_mode(img, color_pages, warmth),
+        None => img,
+    }
+}
+
+pub fn to_color_image(img: &DynamicImage, transparency_support: bool) -> egui::ColorImage {
+    let size = [img.width() as _, img.height() as _];
+    if transparency_support {
+        egui::ColorImage::from_rgba_unmultiplied(
+            size,
+            img.to_rgba8().as_flat_samples().as_slice(),
+        )
+    } else {
+        egui::ColorImage::from_rgb(
+            size,
+            img.to_rgb8().as_raw()
+        )
+    }
+}
+
+struct PageJob {
+    id: u64,
+    recipe: PageRecipe,
+    specs: [Option<PageSpec>; 2],
+    ctx: egui::Context,
+}
+
+/// Makes pages on a worker thread: the full quality pages after a jump while thumbnails
+/// stand in, or the pages for the magnifier. Only the newest job is worked on, the pages
+/// of older jumps are no longer wanted.
+pub struct PageLoader {
+    jobs: Sender<PageJob>,
+    results: Receiver<(u64, PreparedPair)>,
+    next_id: u64,
+}
+
+impl PageLoader {
+    /// `make` is `prepare_pair` or `prepare_full_pair`
+    pub fn new(make: fn(&PageRecipe, &[Option<PageSpec>; 2]) -> PreparedPair) -> Self {
+        let (jobs, job_rx) = channel::<PageJob>();
+        let (results_tx, results) = channel();
+        std::thread::spawn(move || {
+            // Dropping the loader closes the channel and ends the worker
+            while let Ok(job) = job_rx.recv() {
+                let job = job_rx.try_iter().last().unwrap_or(job);
+                let pair = make(&job.recipe, &job.specs);
+                if results_tx.send((job.id, pair)).is_err() {
+                    return;
+                }
+                job.ctx.request_repaint();
+            }
+        });
+        Self { jobs, results, next_id: 0 }
+    }
+
+    /// Queue a pair, returns the id its result comes back with
+    pub fn submit(&mut self, recipe: PageRecipe, specs: [Option<PageSpec>; 2], ctx: &egui::Context) -> u64 {
+        self.next_id += 1;
+        let _ = self.jobs.send(PageJob { id: self.next_id, recipe, specs, ctx: ctx.clone() });
+        self.next_id
+    }
+
+    /// The finished pair of job `id`, results of older jobs are dropped
+    pub fn poll(&self, id: u64) -> Option<PreparedPair> {
+        self.results.try_iter().find(|(job, _)| *job == id).map(|(_, pair)| pair)
+    }
+}
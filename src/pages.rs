@@ -0,0 +1,100 @@
+/// An entry of the page list the reader navigates through
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PageEntry {
+    /// A real image, holds the index into `image_files`
+    Image(usize),
+    /// One half of a double page scan, holds the index into `image_files` and the side
+    Half(usize, HalfSide),
+    /// A virtual blank page, used to fix the double page pairing
+    Blank,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HalfSide {
+    Left,
+    Right,
+}
+
+impl PageEntry {
+    /// Index into `image_files`, `None` for blank pages
+    pub fn file_index(&self) -> Option<usize> {
+        match self {
+            PageEntry::Image(i) | PageEntry::Half(i, _) => Some(*i),
+            PageEntry::Blank => None,
+        }
+    }
+}
+
+/// Build the virtual page list from the image count and the layout of the book.
+/// `blank_pages` holds the image index each blank is inserted before,
+/// `spreads` marks the images split into two pages, shown right half first when `right_first`.
+pub fn build_pages(image_count: usize, blank_pages: &[usize], spreads: &[bool], right_first: bool) -> Vec<PageEntry> {
+    let mut blanks = blank_pages.to_vec();
+    blanks.sort_unstable();
+    let mut blanks = blanks.into_iter().peekable();
+
+    let (first, second) = if right_first { (HalfSide::Right, HalfSide::Left) } else { (HalfSide::Left, HalfSide::Right) };
+
+    let mut pages = Vec::with_capacity(image_count + blank_pages.len());
+    for i in 0..=image_count {
+        while blanks.next_if(|&b| b <= i).is_some() {
+            pages.push(PageEntry::Blank);
+        }
+        if i < image_count {
+            if spreads.get(i).copied().unwrap_or(false) {
+                pages.push(PageEntry::Half(i, first));
+                pages.push(PageEntry::Half(i, second));
+            } else {
+                pages.push(PageEntry::Image(i));
+            }
+        }
+    }
+    pages
+}
+
+/// Image index a blank page at `pos` is anchored to (the next real image after it)
+pub fn blank_anchor(pages: &[PageEntry], pos: usize, image_count: usize) -> usize {
+    pages[pos..].iter()
+        .find_map(|p| p.file_index())
+        .unwrap_or(image_count)
+}
+
+/// Anchor of a blank page after the image list changed, so the blank stays before the
+/// same image. When that image is gone it goes before the next one still there.
+pub fn remap_anchor(anchor: usize, old_files: &[String], new_files: &[String]) -> usize {
+    let new_index = |name: &String| new_files.iter().position(|n| n == name);
+    old_files.get(anchor..).unwrap_or_default().iter().find_map(new_index)
+        .or_else(|| old_files[..anchor.min(old_files.len())].iter().rev().find_map(new_index).map(|i| i + 1))
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blanks_go_before_their_image() {
+        let pages = build_pages(3, &[2, 0], &[], false);
+        assert_eq!(pages, vec![PageEntry::Blank, PageEntry::Image(0), PageEntry::Image(1), PageEntry::Blank, PageEntry::Image(2)]);
+    }
+
+    #[test]
+    fn blank_after_the_last_image() {
+        let pages = build_pages(2, &[2], &[], false);
+        assert_eq!(pages, vec![PageEntry::Image(0), PageEntry::Image(1), PageEntry::Blank]);
+    }
+
+    #[test]
+    fn spreads_split_in_reading_order() {
+        let pages = build_pages(2, &[], &[false, true], true);
+        assert_eq!(pages, vec![PageEntry::Image(0), PageEntry::Half(1, HalfSide::Right), PageEntry::Half(1, HalfSide::Left)]);
+    }
+
+    #[test]
+    fn blank_anchor_is_the_next_image() {
+        let pages = build_pages(2, &[1, 2], &[true, false], false);
+        // Left half, right half, blank, image 1, blank
+        assert_eq!(blank_anchor(&pages, 2, 2), 1);
+        assert_eq!(blank_anchor(&pages, 4, 2), 2);
+    }
+}
@@ -0,0 +1,229 @@
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+use crate::config::CropMargins;
+
+/// A pixel is ink when it differs this much from the gutter colour
+const INK_THRESHOLD: u8 = 40;
+/// Panels smaller than this part of the page are dropped (page numbers, stray marks)
+const MIN_PANEL_AREA: f32 = 0.015;
+/// Deepest nesting of row/column cuts
+const MAX_CUT_DEPTH: u32 = 8;
+
+/// A comic panel, as fractions of the page size
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PanelRect {
+    pub x: f32,
+    pub y: f32,
+    pub w: f32,
+    pub h: f32,
+}
+
+impl PanelRect {
+    pub const FULL_PAGE: PanelRect = PanelRect { x: 0.0, y: 0.0, w: 1.0, h: 1.0 };
+
+    /// The panel in texture coordinates (0..1)
+    pub fn to_uv(self) -> egui::Rect {
+        egui::Rect::from_min_size(egui::pos2(self.x, self.y), egui::vec2(self.w, self.h))
+    }
+
+    pub fn from_uv(uv: egui::Rect) -> Self {
+        let uv = uv.intersect(egui::Rect::from_min_max(egui::Pos2::ZERO, egui::pos2(1.0, 1.0)));
+        PanelRect { x: uv.min.x, y: uv.min.y, w: uv.width(), h: uv.height() }
+    }
+
+    /// The panel on the page cut by `crop`, from its place on the whole page.
+    /// `None` when it is in the margins that were cut.
+    pub fn to_cropped(self, crop: CropMargins) -> Option<Self> {
+        let width = 1.0 - crop.left - crop.right;
+        let height = 1.0 - crop.top - crop.bottom;
+        let min = egui::pos2((self.x - crop.left) / width, (self.y - crop.top) / height);
+        let panel = Self::from_uv(egui::Rect::from_min_size(min, egui::vec2(self.w / width, self.h / height)));
+        (panel.w > 0.0 && panel.h > 0.0).then_some(panel)
+    }
+
+    /// The panel on the whole page, from its place on the page cut by `crop`
+    pub fn to_uncropped(self, crop: CropMargins) -> Self {
+        let width = 1.0 - crop.left - crop.right;
+        let height = 1.0 - crop.top - crop.bottom;
+        PanelRect { x: crop.left + self.x * width, y: crop.top + self.y * height, w: self.w * width, h: self.h * height }
+    }
+}
+
+/// Pixel region of the analysed page, `x1`/`y1` exclusive
+#[derive(Clone, Copy)]
+struct Region {
+    x0: u32,
+    y0: u32,
+    x1: u32,
+    y1: u32,
+}
+
+/// Ink mask of the analysed page
+struct InkMask {
+    ink: Vec<bool>,
+    width: u32,
+}
+
+impl InkMask {
+    fn at(&self, x: u32, y: u32) -> bool {
+        self.ink[(y * self.width + x) as usize]
+    }
+
+    fn row_ink(&self, y: u32, region: Region) -> u32 {
+        (region.x0..region.x1).filter(|&x| self.at(x, y)).count() as u32
+    }
+
+    fn col_ink(&self, x: u32, region: Region) -> u32 {
+        (region.y0..region.y1).filter(|&y| self.at(x, y)).count() as u32
+    }
+
+    /// Shrink a region to the bounding box of its ink, `None` when it's empty
+    fn trim(&self, region: Region) -> Option<Region> {
+        let rows: Vec<u32> = (region.y0..region.y1).filter(|&y| self.row_ink(y, region) > 0).collect();
+        let cols: Vec<u32> = (region.x0..region.x1).filter(|&x| self.col_ink(x, region) > 0).collect();
+        Some(Region {
+            x0: *cols.first()?,
+            y0: *rows.first()?,
+            x1: *cols.last()? + 1,
+            y1: *rows.last()? + 1,
+        })
+    }
+}
+
+/// Detect the panels of a page, in reading order.
+/// The page is cut recursively along its gutters: first into tiers (rows),
+/// then each tier into columns, and so on.
+pub fn detect_panels(img: &DynamicImage, right_to_left: bool) -> Vec<PanelRect> {
+    // Gutters are wide, a small copy is enough to find them
+    let small = img.thumbnail(800, 800).to_luma8();
+    let (w, h) = small.dimensions();
+    if w < 16 || h < 16 {
+        return vec![PanelRect::FULL_PAGE];
+    }
+
+    // Gutter colour from the page corners, white on most pages and black on some
+    let mut corners = [(0, 0), (w - 1, 0), (0, h - 1), (w - 1, h - 1)].map(|(x, y)| small.get_pixel(x, y)[0]);
+    corners.sort_unstable();
+    let background = ((corners[1] as u16 + corners[2] as u16) / 2) as u8;
+
+    let mask = InkMask {
+        ink: small.pixels().map(|p| p[0].abs_diff(background) > INK_THRESHOLD).collect(),
+        width: w,
+    };
+
+    let mut regions = Vec::new();
+    xy_cut(&mask, Region { x0: 0, y0: 0, x1: w, y1: h }, right_to_left, 0, &mut regions);
+
+    let page_area = (w * h) as f32;
+    let panels: Vec<PanelRect> = regions.into_iter()
+        .filter(|r| ((r.x1 - r.x0) * (r.y1 - r.y0)) as f32 / page_area >= MIN_PANEL_AREA)
+        .map(|r| PanelRect {
+            x: r.x0 as f32 / w as f32,
+            y: r.y0 as f32 / h as f32,
+            w: (r.x1 - r.x0) as f32 / w as f32,
+            h: (r.y1 - r.y0) as f32 / h as f32,
+        })
+        .collect();
+
+    if panels.is_empty() { vec![PanelRect::FULL_PAGE] } else { panels }
+}
+
+fn xy_cut(mask: &InkMask, region: Region, right_to_left: bool, depth: u32, out: &mut Vec<Region>) {
+    let Some(region) = mask.trim(region) else { return };
+    if depth >= MAX_CUT_DEPTH {
+        out.push(region);
+        return;
+    }
+
+    // Tiers are read from top to bottom
+    let rows = split(region.y0, region.y1, |y| mask.row_ink(y, region), region.x1 - region.x0);
+    if rows.len() > 1 {
+        for (y0, y1) in rows {
+            xy_cut(mask, Region { y0, y1, ..region }, right_to_left, depth + 1, out);
+        }
+        return;
+    }
+
+    // Panels in a tier are read from the right in manga
+    let mut cols = split(region.x0, region.x1, |x| mask.col_ink(x, region), region.y1 - region.y0);
+    if cols.len() > 1 {
+        if right_to_left {
+            cols.reverse();
+        }
+        for (x0, x1) in cols {
+            xy_cut(mask, Region { x0, x1, ..region }, right_to_left, depth + 1, out);
+        }
+        return;
+    }
+
+    out.push(region);
+}
+
+/// Split `start..end` at the gutters: runs of lines with (almost) no ink.
+/// `line_length` is the length of one line, used for the noise allowance.
+fn split(start: u32, end: u32, ink_at: impl Fn(u32) -> u32, line_length: u32) -> Vec<(u32, u32)> {
+    let noise = line_length / 100;
+    let min_gap = ((end - start) / 120).max(2);
+
+    let mut segments = Vec::new();
+    let mut segment_start: Option<u32> = None;
+    let mut gap = 0;
+    for i in start..end {
+        if ink_at(i) > noise {
+            if segment_start.is_none() {
+                segment_start = Some(i);
+            } else if gap >= min_gap {
+                // The gap was a gutter: close the previous segment
+                let s = segment_start.unwrap();
+                segments.push((s, i - gap));
+                segment_start = Some(i);
+            }
+            gap = 0;
+        } else if segment_start.is_some() {
+            gap += 1;
+        }
+    }
+    if let Some(s) = segment_start {
+        segments.push((s, end - gap));
+    }
+    segments
+}
+
+/// Put hand drawn panels in reading order: tier by tier from the top, then by column
+pub fn sort_reading_order(panels: &mut [PanelRect], right_to_left: bool) {
+    panels.sort_by(|a, b| a.y.total_cmp(&b.y));
+
+    // A panel whose middle is below the current tier starts a new tier
+    let mut tiers: Vec<usize> = Vec::with_capacity(panels.len());
+    let mut tier = 0;
+    let mut tier_bottom = f32::MIN;
+    for p in panels.iter() {
+        if p.y + p.h / 2.0 > tier_bottom {
+            if !tiers.is_empty() {
+                tier += 1;
+            }
+            tier_bottom = p.y + p.h;
+        }
+        tiers.push(tier);
+    }
+
+    let mut keyed: Vec<(usize, PanelRect)> = tiers.into_iter().zip(panels.iter().copied()).collect();
+    keyed.sort_by(|(ta, a), (tb, b)| {
+        ta.cmp(tb).then_with(|| {
+            if right_to_left { b.x.total_cmp(&a.x) } else { a.x.total_cmp(&b.x) }
+        })
+    });
+    for (slot, (_, p)) in panels.iter_mut().zip(keyed) {
+        *slot = p;
+    }
+}
+
+/// Panel under a texture coordinate, the smallest one when they overlap
+pub fn panel_at(panels: &[PanelRect], uv: egui::Pos2) -> Option<usize> {
+    panels.iter()
+        .enumerate()
+        .filter(|(_, p)| p.to_uv().contains(uv))
+        .min_by(|(_, a), (_, b)| (a.w * a.h).total_cmp(&(b.w * b.h)))
+        .map(|(i, _)| i)
+}
+
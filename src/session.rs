@@ -0,0 +1,60 @@
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use crate::config::PageViewOptions;
+use crate::utils::{exe_dir_file, write_atomic};
+
+const SESSION_FILE: &str = "session.json";
+
+/// What was on screen when the reader was last closed, reopened on the next start
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct Session {
+    pub book: Option<PathBuf>,
+    pub page: usize,
+    pub is_shifted: bool,
+    pub view_mode: Option<PageViewOptions>,
+    pub zoom: f32,
+    pub scroll_offset: [f32; 2],
+    pub fullscreen: bool,
+    pub maximized: bool,
+    /// Outer position and inner size of the window when it was neither maximized nor fullscreen
+    pub window_pos: Option<[f32; 2]>,
+    pub window_size: Option<[f32; 2]>,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self {
+            book: None,
+            page: 0,
+            is_shifted: false,
+            view_mode: None,
+            zoom: 1.0,
+            scroll_offset: [0.0, 0.0],
+            fullscreen: false,
+            maximized: true,
+            window_pos: None,
+            window_size: None,
+        }
+    }
+}
+
+impl Session {
+    /// The saved session, `None` when there is none (restoring is off) or it can't be read
+    pub fn load() -> Option<Self> {
+        std::fs::read_to_string(exe_dir_file(SESSION_FILE))
+            .ok()
+            .and_then(|data| serde_json::from_str(&data).ok())
+    }
+
+    /// Written through a temporary file, so a killed process leaves the previous session intact
+    pub fn save(&self) {
+        if let Ok(json) = serde_json::to_string_pretty(self) {
+            let _ = write_atomic(&exe_dir_file(SESSION_FILE), json.as_bytes());
+        }
+    }
+
+    pub fn clear() {
+        let _ = std::fs::remove_file(exe_dir_file(SESSION_FILE));
+    }
+}
@@ -0,0 +1,261 @@
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use image::{DynamicImage, ImageFormat};
+use pdfium_render::prelude::Pixels;
+use crate::config::{SortOrder, SourceMode};
+use crate::ordering::sort_path_strings;
+use crate::utils::windows_natural_sort_strings;
+
+/// Image files shown as pages
+pub const IMAGE_EXTENSIONS: [&str; 9] = ["png", "jpg", "jpeg", "bmp", "webp", "gif", "tiff", "tga", "avif"];
+
+/// How a path is read, from its extension. Anything else is a folder of images
+/// (or an image inside one).
+pub fn source_mode_of(path: &Path) -> SourceMode {
+    let extension = path.extension().map_or("".to_string(), |ext| ext.to_string_lossy().to_lowercase());
+    match extension.as_str() {
+        "zip" | "cbz" => SourceMode::Zip,
+        "cbr" | "rar" => SourceMode::Rar,
+        "pdf" => SourceMode::Pdf,
+        _ => SourceMode::Folder,
+    }
+}
+
+/// Names of the pages of a source in reading order: archive entries, image paths
+/// of a folder or the virtual names of the pdf pages. The images of a folder are
+/// ordered by `folder_order`, the entries of an archive by their natural name.
+pub fn list_pages(mode: SourceMode, path: &Path, folder_order: SortOrder) -> Vec<String> {
+    let exts = IMAGE_EXTENSIONS;
+    let mut images = Vec::new();
+    match mode {
+        SourceMode::Zip => {
+            let file = match File::open(path) {
+                Ok(f) => f,
+                Err(_) => return images,
+            };
+            if let Ok(mut archive) = zip::ZipArchive::new(file) {
+                for i in 0..archive.len() {
+                    if let Ok(f) = archive.by_index(i) {
+                        let name = f.name().to_lowercase();
+                        if exts.iter().any(|&e| name.ends_with(&format!(".{}", e))) {
+                            images.push(f.name().to_string());
+                        }
+                    }
+                }
+            }
+        }
+        SourceMode::Rar => {
+            if let Ok(archive) = unrar::Archive::new(path).open_for_listing() {
+                for entry in archive {
+                    if let Ok(e) = entry {
+                        // Convert Option<&str> to String safely
+                        if let Some(name_str) = e.filename.to_str() {
+                            let name = name_str.to_string();
+                            // Check if it's an image extension
+                            if exts.iter().any(|&e_ext| name.to_lowercase().ends_with(e_ext)) {
+                                images.push(name);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        SourceMode::Pdf => {
+            // Initialize Pdfium (you may need to bundle the dll/so/dylib)
+            let pdfium = pdfium_render::prelude::Pdfium::default();
+            if let Ok(doc) = pdfium.load_pdf_from_file(path, None) {
+                let page_count = doc.pages().len();
+                for i in 0..page_count {
+                    // We use a virtual naming scheme for PDF pages in our image_files list
+                    images.push(format!("pdf_page_{}", i));
+                }
+            }
+        }
+        SourceMode::Folder => {
+            if let Ok(entries) = fs::read_dir(path) {
+                for entry in entries.flatten() {
+                    let p = entry.path();
+                    if p.is_file() && exts.iter().any(|&e| p.extension().map_or(false, |ext| ext.to_string_lossy().to_lowercase() == e)) {
+                        images.push(p.to_string_lossy().to_string());
+                    }
+                }
+            }
+        }
+    }
+
+    if mode == SourceMode::Folder {
+        sort_path_strings(&mut images, folder_order);
+    } else {
+        windows_natural_sort_strings(&mut images);
+    }
+    images
+}
+
+/// Reads the pages of one source (zip, rar, pdf or folder). It holds no UI state,
+/// so the thumbnail workers can use it too.
+pub struct PageReader {
+    mode: SourceMode,
+    path: PathBuf,
+    archive: Option<zip::ZipArchive<File>>,
+    byte_fix: bool,
+}
+
+impl PageReader {
+    /// `byte_fix` strips broken Adobe segments from jpegs before decoding them
+    pub fn open(mode: SourceMode, path: &Path, byte_fix: bool) -> Self {
+        let archive = if mode == SourceMode::Zip {
+            File::open(path).ok().and_then(|f| zip::ZipArchive::new(f).ok())
+        } else {
+            None
+        };
+        Self { mode, path: path.to_path_buf(), archive, byte_fix }
+    }
+
+    pub fn mode(&self) -> SourceMode {
+        self.mode
+    }
+
+    /// Decode one page. `name` is the entry of the image list, `index` its position,
+    /// used for pdf pages which are rendered `pdf_height` pixels high.
+    pub fn decode(&mut self, name: &str, index: usize, pdf_height: f32) -> Option<DynamicImage> {
+        if self.mode == SourceMode::Pdf {
+            return render_pdf_page(&self.path, index, pdf_height);
+        }
+
+        let mut buffer = self.read_bytes(name)?;
+        if self.byte_fix {
+            buffer = strip_adobe_app14_if_invalid(&buffer);
+        }
+        match image::guess_format(&buffer) {
+            Ok(format) => image::load_from_memory_with_format(&buffer, format).ok(),
+            // Fallback: If guessing fails, try loading as TGA
+            // since TGA is often the one that fails detection.
+            Err(_) => image::load_from_memory_with_format(&buffer, ImageFormat::Tga).ok(),
+        }
+    }
+
+    /// Raw bytes of an image file or archive entry
+    pub fn read_bytes(&mut self, filename: &str) -> Option<Vec<u8>> {
+        if self.mode == SourceMode::Folder {
+            fs::read(filename).ok() // Load directly from path
+        } else if let Some(arc) = &mut self.archive {
+            arc.by_name(filename).ok().and_then(|mut f| {
+                let mut b = Vec::new();
+                f.read_to_end(&mut b).ok().map(|_| b)
+            })
+        } else  if self.mode == SourceMode::Rar {
+            unrar::Archive::new(&self.path).open_for_processing().ok().and_then(|rar_achive| {
+                let mut cursor = rar_achive.read_header().ok().flatten();
+                loop {
+                    match cursor {
+                        Some(e) => {
+                            // Use .entry() before reference filename
+                            let current_name = e.entry().filename.to_str();
+
+                            if let Some(name_str) = current_name {
+                                if name_str == filename {
+                                    break e.read().ok().map(|(bytes, _arc)| bytes);
+                                } else {
+                                    cursor = e.skip().ok().and_then(|arc| arc.read_header().ok().flatten());
+                                }
+                            } else {
+                                // Filename wasn't valid UTF-8, skip it
+                                cursor = e.skip().ok().and_then(|arc| arc.read_header().ok().flatten());
+                            }
+                        }
+                        None => break None,
+                    }
+                }
+            })
+        } else {
+            None
+        }
+    }
+}
+
+pub fn strip_adobe_app14_if_invalid(bytes: &[u8]) -> Vec<u8> {
+    let mut i = 2;
+    let mut out = Vec::with_capacity(bytes.len());
+
+    // Copy SOI first (must be first two bytes)
+    if bytes.len() < 2 || bytes[0] != 0xFF || bytes[1] != 0xD8 {
+        return bytes.to_vec(); // not a valid jpeg
+    }
+
+    out.extend_from_slice(&bytes[0..2]);
+
+    while i < bytes.len() {
+        if i + 1 >= bytes.len() {
+            break;
+        }
+
+        // Every marker must start with FF
+        if bytes[i] != 0xFF {
+            // Start of entropy data (after SOS)
+            out.extend_from_slice(&bytes[i..]);
+            break;
+        }
+
+        let marker = bytes[i + 1];
+
+        // Standalone markers (no length)
+        if marker == 0xD9 || (0xD0..=0xD7).contains(&marker) {
+            out.push(0xFF);
+            out.push(marker);
+            i += 2;
+            continue;
+        }
+
+        // SOS marker → copy rest of file and stop parsing
+        if marker == 0xDA {
+            out.extend_from_slice(&bytes[i..]);
+            break;
+        }
+
+        if i + 4 > bytes.len() {
+            break;
+        }
+
+        let length =
+            u16::from_be_bytes([bytes[i + 2], bytes[i + 3]]) as usize;
+
+        let segment_end = i + 2 + length;
+
+        if segment_end > bytes.len() {
+            break;
+        }
+
+        // If APP14 (FF EE)
+        if marker == 0xEE
+            && length >= 14
+            && &bytes[i + 4..i + 9] == b"Adobe"
+        {
+            println!("Stripping Adobe APP14 segment");
+            // Skip this segment entirely
+            i = segment_end;
+            continue;
+        }
+
+        // Otherwise copy full segment
+        out.extend_from_slice(&bytes[i..segment_end]);
+        i = segment_end;
+    }
+
+    out
+}
+
+/// Helper to render a specific page, `target_h` pixels high
+pub fn render_pdf_page(path: &Path, index: usize, target_h: f32) -> Option<DynamicImage> {
+    let pdfium = pdfium_render::prelude::Pdfium::default();
+    let doc = pdfium.load_pdf_from_file(path, None).ok()?;
+    let page = doc.pages().get(index as u16).ok()?;
+    let width_inch = page.width().value ;
+    let height_inch = page.height().value;
+
+    let h_ratio = target_h / height_inch;
+    let target_w = width_inch * h_ratio;
+
+    let bitmap = page.render(target_w as Pixels, target_h as Pixels, None).ok()?;
+    Some(bitmap.as_image()) // pdfium-render integrates with the 'image' crate
+}
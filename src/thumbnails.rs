@@ -0,0 +1,203 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::time::{SystemTime, UNIX_EPOCH};
+use eframe::egui;
+use image::DynamicImage;
+use crate::config::SourceMode;
+use crate::source::PageReader;
+use crate::utils::exe_dir_file;
+
+/// Height of the thumbnails in pixels
+pub const THUMBNAIL_HEIGHT: u32 = 240;
+/// Folder next to the exe where thumbnails are kept between sessions
+const THUMBNAIL_DIR: &str = "thumbnails";
+/// Size the thumbnail folder may grow to, the least recently used thumbnails go beyond it
+const THUMBNAIL_DIR_MAX_BYTES: u64 = 256 * 1024 * 1024;
+
+struct ThumbnailRequest {
+    file_index: usize,
+    name: String,
+}
+
+/// (source generation, image index, thumbnail)
+type ThumbnailResult = (u64, usize, Option<egui::ColorImage>);
+
+/// Small images of the pages of the current source, decoded on a worker thread.
+/// The newest request is served first, so scrubbing shows the page under the handle quickly.
+pub struct ThumbnailCache {
+    requests: Option<Sender<ThumbnailRequest>>,
+    results_tx: Sender<ThumbnailResult>,
+    results: Receiver<ThumbnailResult>,
+    generation: u64,
+    textures: HashMap<usize, egui::TextureHandle>,
+    requested: HashSet<usize>,
+}
+
+impl ThumbnailCache {
+    pub fn new() -> Self {
+        // Once a run, off the UI thread: a large folder takes a while to list
+        std::thread::spawn(|| prune_thumbnail_dir(&thumbnail_dir(), THUMBNAIL_DIR_MAX_BYTES));
+        let (results_tx, results) = channel();
+        Self {
+            requests: None,
+            results_tx,
+            results,
+            generation: 0,
+            textures: HashMap::new(),
+            requested: HashSet::new(),
+        }
+    }
+
+    /// Forget the thumbnails and stop the worker, closing the source it reads
+    pub fn clear(&mut self) {
+        self.generation += 1;
+        self.textures.clear();
+        self.requested.clear();
+        self.requests = None;
+    }
+
+    /// Forget the thumbnails of the previous source and start a worker for the new one
+    pub fn set_source(&mut self, mode: SourceMode, path: PathBuf, byte_fix: bool, ctx: &egui::Context) {
+        self.generation += 1;
+        self.textures.clear();
+        self.requested.clear();
+
+        // Dropping the old sender stops the old worker
+        let (tx, rx) = channel::<ThumbnailRequest>();
+        self.requests = Some(tx);
+        let results = self.results_tx.clone();
+        let generation = self.generation;
+        let ctx = ctx.clone();
+        std::thread::spawn(move || {
+            let mut reader = PageReader::open(mode, &path, byte_fix);
+            let cache_dir = thumbnail_dir();
+            let source_mtime = modified_time(&path);
+            let mut stack: Vec<ThumbnailRequest> = Vec::new();
+            loop {
+                if stack.is_empty() {
+                    match rx.recv() {
+                        Ok(request) => stack.push(request),
+                        Err(_) => return,
+                    }
+                }
+                stack.extend(rx.try_iter());
+                let Some(request) = stack.pop() else { continue };
+
+                let thumbnail = load_thumbnail(&mut reader, &cache_dir, &path, source_mtime, &request.name, request.file_index);
+                if results.send((generation, request.file_index, thumbnail)).is_err() {
+                    return;
+                }
+                ctx.request_repaint();
+            }
+        });
+    }
+
+    /// Ask for the thumbnail of an image, unless it is already there or on its way
+    pub fn request(&mut self, file_index: usize, name: &str) {
+        if !self.requested.insert(file_index) {
+            return;
+        }
+        if let Some(requests) = &self.requests {
+            let _ = requests.send(ThumbnailRequest { file_index, name: name.to_string() });
+        }
+    }
+
+    pub fn get(&self, file_index: usize) -> Option<&egui::TextureHandle> {
+        self.textures.get(&file_index)
+    }
+
+    /// Upload the thumbnails the worker finished
+    pub fn poll(&mut self, ctx: &egui::Context) {
+        for (generation, file_index, thumbnail) in self.results.try_iter() {
+            if generation != self.generation {
+                continue;
+            }
+            if let Some(thumbnail) = thumbnail {
+                let texture = ctx.load_texture(format!("thumbnail#{}", file_index), thumbnail, egui::TextureOptions::LINEAR);
+                self.textures.insert(file_index, texture);
+            }
+        }
+    }
+}
+
+/// Folder of the cached thumbnails, created when missing
+pub fn thumbnail_dir() -> PathBuf {
+    let dir = exe_dir_file(THUMBNAIL_DIR);
+    let _ = std::fs::create_dir_all(&dir);
+    dir
+}
+
+/// Thumbnail of one page of `source`, read from `cache_dir` when it was made before
+/// and saved there otherwise. `source_mtime` is the time of the source file.
+pub fn load_thumbnail(reader: &mut PageReader, cache_dir: &Path, source: &Path, source_mtime: u64, name: &str, index: usize) -> Option<egui::ColorImage> {
+    // Images of a folder are files of their own, with their own time
+    let mtime = if reader.mode() == SourceMode::Folder { modified_time(Path::new(name)) } else { source_mtime };
+    let cache_file = cache_dir.join(format!("{:016x}.jpg", cache_key(source, mtime, name)));
+
+    let cached = image::open(&cache_file).ok();
+    if cached.is_some() {
+        // The file time tells `prune_thumbnail_dir` when the thumbnail was last used
+        let _ = std::fs::File::options().write(true).open(&cache_file)
+            .and_then(|file| file.set_modified(SystemTime::now()));
+    }
+    let thumbnail = cached.or_else(|| {
+        let thumb = reader.decode(name, index, THUMBNAIL_HEIGHT as f32)?
+            .thumbnail(u32::MAX, THUMBNAIL_HEIGHT);
+        let thumb = DynamicImage::ImageRgb8(thumb.to_rgb8());
+        let _ = thumb.save_with_format(&cache_file, image::ImageFormat::Jpeg);
+        Some(thumb)
+    })?;
+    let thumb = thumbnail.to_rgba8();
+    Some(egui::ColorImage::from_rgba_unmultiplied([thumb.width() as _, thumb.height() as _], thumb.as_raw()))
+}
+
+/// Seconds since the epoch of the last change of a file
+pub fn modified_time(path: &Path) -> u64 {
+    std::fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_secs())
+}
+
+/// Delete the least recently used thumbnails until the folder is below `max_bytes`.
+/// Thumbnails of rewritten or deleted books are never used again and go first.
+fn prune_thumbnail_dir(dir: &Path, max_bytes: u64) {
+    let Ok(entries) = std::fs::read_dir(dir) else { return };
+    let mut files: Vec<(SystemTime, u64, PathBuf)> = entries.flatten()
+        .filter_map(|entry| {
+            let meta = entry.metadata().ok().filter(|meta| meta.is_file())?;
+            Some((meta.modified().unwrap_or(UNIX_EPOCH), meta.len(), entry.path()))
+        })
+        .collect();
+    let mut total: u64 = files.iter().map(|(_, len, _)| len).sum();
+    if total <= max_bytes {
+        return;
+    }
+    // Down to 3/4 of the limit, so it isn't hit again right away
+    let target = max_bytes / 4 * 3;
+    files.sort_unstable_by_key(|(modified, _, _)| *modified);
+    for (_, len, path) in files {
+        if total <= target {
+            break;
+        }
+        if std::fs::remove_file(&path).is_ok() {
+            total -= len;
+        }
+    }
+}
+
+/// Name of a cached thumbnail: FNV-1a of the source path, its time and the entry,
+/// stable between runs unlike the std hasher
+fn cache_key(source: &Path, mtime: u64, entry: &str) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    let source = source.to_string_lossy();
+    for chunk in [source.as_bytes(), &mtime.to_le_bytes(), entry.as_bytes()] {
+        for &byte in chunk.iter().chain(&[0u8]) {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    }
+    hash
+}
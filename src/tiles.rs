@@ -0,0 +1,74 @@
+use eframe::egui;
+use egui::{Color32, ColorImage, Painter, Rect, TextureHandle, TextureOptions};
+
+/// Side of one tile in pixels, well below the texture limit of every GPU
+const TILE_SIZE: usize = 2048;
+
+/// A page too large for a single texture. The pixels stay in memory and only
+/// the tiles drawn on screen are uploaded to the GPU.
+pub struct TiledImage {
+    image: ColorImage,
+    options: TextureOptions,
+    columns: usize,
+    tiles: Vec<Option<TextureHandle>>,
+    used: Vec<bool>,
+}
+
+impl TiledImage {
+    pub fn new(image: ColorImage, options: TextureOptions) -> Self {
+        let columns = image.width().div_ceil(TILE_SIZE);
+        let rows = image.height().div_ceil(TILE_SIZE);
+        Self {
+            image,
+            options,
+            columns,
+            tiles: vec![None; columns * rows],
+            used: vec![false; columns * rows],
+        }
+    }
+
+    /// Draw the tiles of the image shown at `rect` that are inside the painter's clip rect
+    pub fn paint(&mut self, painter: &Painter, rect: Rect, name: &str) {
+        let [width, height] = self.image.size;
+        let scale = rect.size() / egui::vec2(width as f32, height as f32);
+        let clip = painter.clip_rect();
+
+        for (index, tile) in self.tiles.iter_mut().enumerate() {
+            let (x0, y0) = ((index % self.columns) * TILE_SIZE, (index / self.columns) * TILE_SIZE);
+            let (x1, y1) = ((x0 + TILE_SIZE).min(width), (y0 + TILE_SIZE).min(height));
+            let tile_rect = Rect::from_min_max(
+                rect.min + egui::vec2(x0 as f32, y0 as f32) * scale,
+                rect.min + egui::vec2(x1 as f32, y1 as f32) * scale,
+            );
+            if !tile_rect.intersects(clip) {
+                continue;
+            }
+
+            // Each tile carries a one pixel border of its neighbours, so the
+            // linear filtering at the edges blends into them without seams
+            let (ex0, ey0) = (x0.saturating_sub(1), y0.saturating_sub(1));
+            let (ex1, ey1) = ((x1 + 1).min(width), (y1 + 1).min(height));
+            let texture = tile.get_or_insert_with(|| {
+                let pixels = self.image.region_by_pixels([ex0, ey0], [ex1 - ex0, ey1 - ey0]);
+                painter.ctx().load_texture(format!("{}#tile{}", name, index), pixels, self.options)
+            });
+            let (ew, eh) = ((ex1 - ex0) as f32, (ey1 - ey0) as f32);
+            let uv = Rect::from_min_max(
+                egui::pos2((x0 - ex0) as f32 / ew, (y0 - ey0) as f32 / eh),
+                egui::pos2((x1 - ex0) as f32 / ew, (y1 - ey0) as f32 / eh),
+            );
+            painter.image(texture.id(), tile_rect, uv, Color32::WHITE);
+            self.used[index] = true;
+        }
+    }
+
+    /// Free the tiles that were not drawn since the last call
+    pub fn evict_unused(&mut self) {
+        for (tile, used) in self.tiles.iter_mut().zip(self.used.iter_mut()) {
+            if !*used {
+                *tile = None;
+            }
+            *used = false;
+        }
+    }
+}
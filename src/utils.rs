@@ -1,3 +1,4 @@
+use std::ffi::OsStr;
 use std::os::windows::ffi::OsStrExt;
 use std::path::{Path, PathBuf};
 use windows::core::PCWSTR;
@@ -22,6 +23,17 @@ pub fn windows_natural_sort(paths: &mut [PathBuf]) {
     });
 }
 
+/// Windows-native natural comparison of two names, for sorting things other than plain lists
+pub fn windows_natural_cmp(a: &OsStr, b: &OsStr) -> std::cmp::Ordering {
+    let a_name: Vec<u16> = a.encode_wide().chain(Some(0)).collect();
+    let b_name: Vec<u16> = b.encode_wide().chain(Some(0)).collect();
+
+    let result = unsafe {
+        StrCmpLogicalW(PCWSTR(a_name.as_ptr()), PCWSTR(b_name.as_ptr()))
+    };
+    result.cmp(&0)
+}
+
 /// Natural alphanumeric sorting specifically for String vectors
 pub fn windows_natural_sort_strings(strings: &mut [String]) {
     strings.sort_by(|a, b| {
@@ -39,4 +51,22 @@ pub fn windows_natural_sort_strings(strings: &mut [String]) {
             _ => std::cmp::Ordering::Equal,
         }
     });
-}
\ No newline at end of file
+}
+
+/// Path of a file stored next to the executable (settings, per-book data, ...)
+pub fn exe_dir_file(name: &str) -> PathBuf {
+    let mut exe_path = std::env::current_exe().expect("Failed to get current exe path");
+    exe_path.pop();
+    exe_path.push(name);
+    exe_path
+}
+
+/// Write a file through a temporary one renamed over it, so a crash or power loss
+/// leaves either the old or the new content, never half of it
+pub fn write_atomic(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    let tmp = path.with_extension("tmp");
+    let mut file = std::fs::File::create(&tmp)?;
+    std::io::Write::write_all(&mut file, data)?;
+    file.sync_all()?;
+    std::fs::rename(&tmp, path)
+}
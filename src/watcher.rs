@@ -0,0 +1,86 @@
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver};
+use std::time::{Duration, Instant};
+use eframe::egui;
+use notify::event::ModifyKind;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+/// Quiet time before changes are reported, so a chapter arriving file by file is one refresh
+const SETTLE_TIME: Duration = Duration::from_millis(500);
+
+/// Which watched folders changed
+#[derive(Default, Clone)]
+pub struct FolderChanges {
+    /// Images of the open folder source
+    pub pages: bool,
+    /// Files of the open folder source that were written, added or removed
+    pub changed_pages: Vec<PathBuf>,
+    /// Books next to the open one
+    pub siblings: bool,
+}
+
+/// Watches the open book's folder for new or removed pages, and the folder holding
+/// the book for new or removed books
+pub struct FolderWatcher {
+    _watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<Event>>,
+    pages_dir: Option<PathBuf>,
+    parent: PathBuf,
+    pending: FolderChanges,
+    last_event: Option<Instant>,
+}
+
+impl FolderWatcher {
+    /// `pages_dir` is the open folder source, `None` for archives
+    pub fn new(pages_dir: Option<&Path>, parent: &Path, ctx: &egui::Context) -> Option<Self> {
+        let (tx, events) = channel();
+        let ctx = ctx.clone();
+        let mut watcher = notify::recommended_watcher(move |event| {
+            let _ = tx.send(event);
+            ctx.request_repaint();
+        }).ok()?;
+        watcher.watch(parent, RecursiveMode::NonRecursive).ok()?;
+        if let Some(dir) = pages_dir {
+            watcher.watch(dir, RecursiveMode::NonRecursive).ok()?;
+        }
+        Some(Self {
+            _watcher: watcher,
+            events,
+            pages_dir: pages_dir.map(Path::to_path_buf),
+            parent: parent.to_path_buf(),
+            pending: FolderChanges::default(),
+            last_event: None,
+        })
+    }
+
+    /// The changes since the last report, once the folders have been quiet for a moment
+    pub fn poll(&mut self, ctx: &egui::Context) -> Option<FolderChanges> {
+        for event in self.events.try_iter().flatten() {
+            // Reading the pages opens them too
+            if matches!(event.kind, EventKind::Access(_) | EventKind::Modify(ModifyKind::Metadata(_))) {
+                continue;
+            }
+            for path in &event.paths {
+                let folder = path.parent();
+                if folder.is_some() && folder == self.pages_dir.as_deref() {
+                    self.pending.pages = true;
+                    if !self.pending.changed_pages.contains(path) {
+                        self.pending.changed_pages.push(path.clone());
+                    }
+                }
+                if folder == Some(self.parent.as_path()) {
+                    self.pending.siblings = true;
+                }
+            }
+            self.last_event = Some(Instant::now());
+        }
+
+        let quiet = self.last_event?.elapsed();
+        if quiet < SETTLE_TIME {
+            ctx.request_repaint_after(SETTLE_TIME - quiet);
+            return None;
+        }
+        self.last_event = None;
+        Some(std::mem::take(&mut self.pending))
+    }
+}